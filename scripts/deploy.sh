@@ -208,9 +208,32 @@ instantiate_contract() {
     INSTANTIATE_MSG='{
         "admin": "'$ADMIN_ADDRESS'",
         "config": {
-            "auto_claim_enabled": true,
             "max_rewards_per_user": 1000,
-            "reward_expiration_days": 30
+            "reward_expiration_days": 30,
+            "auto_claim_enabled": true,
+            "manual_claim_reward_types": ["nft"],
+            "payout_method": { "native": { "denom": "uluckee" } },
+            "mint_cap": "0",
+            "referral_rates": ["0.5", "0.2", "0.1"],
+            "level_thresholds": [
+                { "level": "bronze", "min_points": 0 },
+                { "level": "silver", "min_points": 1000 },
+                { "level": "gold", "min_points": 5000 }
+            ],
+            "level_demotion_enabled": false,
+            "level_up_reward": "0",
+            "level_multipliers": [
+                { "level": "silver", "multiplier": "1.1" },
+                { "level": "gold", "multiplier": "1.2" }
+            ],
+            "max_batch_size": 100,
+            "points_exchange": {
+                "rate": "1",
+                "min_points": "1000",
+                "max_points": "0",
+                "user_daily_cap": "0",
+                "global_daily_cap": "0"
+            }
         }
     }'
     
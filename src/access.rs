@@ -0,0 +1,58 @@
+use cosmwasm_std::{Addr, Deps, StdResult, Storage};
+
+use crate::error::ContractError;
+use crate::msg::{ContractStatus, Role};
+use crate::state::{ADMIN, CONTRACTS, CONTRACT_ADDRS, ROLES};
+
+// ===== 权限控制 =====
+//
+// 管理员拥有全部权限；其他地址按角色授权，注册合约按能力授权。
+// 放弃管理员后只剩角色与能力授权，仅限管理员的操作不再可用。
+
+pub fn is_admin(storage: &dyn Storage, sender: &Addr) -> StdResult<bool> {
+    Ok(ADMIN.may_load(storage)?.as_ref() == Some(sender))
+}
+
+pub fn has_role(storage: &dyn Storage, sender: &Addr, role: &Role) -> bool {
+    ROLES.has(storage, (role.key().to_string(), sender.to_string()))
+}
+
+/// 仅管理员可执行
+pub fn ensure_admin(storage: &dyn Storage, sender: &Addr) -> Result<(), ContractError> {
+    if !is_admin(storage, sender)? {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
+
+/// 管理员或被授予 `role` 的地址可执行
+pub fn ensure_role(storage: &dyn Storage, sender: &Addr, role: &Role) -> Result<(), ContractError> {
+    if !is_admin(storage, sender)? && !has_role(storage, sender, role) {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
+
+/// 在 `ensure_role` 基础上，允许拥有 `capability` 且处于启用状态的注册合约执行
+pub fn ensure_role_or_capability(
+    deps: Deps,
+    sender: &Addr,
+    role: &Role,
+    capability: &str,
+) -> Result<(), ContractError> {
+    if ensure_role(deps.storage, sender, role).is_ok() {
+        return Ok(());
+    }
+
+    let contract_type = CONTRACT_ADDRS
+        .may_load(deps.storage, sender.to_string())?
+        .ok_or(ContractError::Unauthorized {})?;
+    let contract_info = CONTRACTS.load(deps.storage, contract_type)?;
+    if !contract_info.capabilities.iter().any(|c| c == capability) {
+        return Err(ContractError::Unauthorized {});
+    }
+    if contract_info.status != ContractStatus::Active {
+        return Err(ContractError::ContractNotActive {});
+    }
+    Ok(())
+}
@@ -1,19 +1,39 @@
 use cosmwasm_std::{
-    entry_point, to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Uint128,
+    entry_point, from_json, to_json_binary, Addr, BankMsg, Binary, Coin, CosmosMsg, Deps, DepsMut, Env,
+    Decimal, MessageInfo, Reply, Response, StdError, StdResult, Storage, SubMsg, SubMsgResult, Uint128, WasmMsg,
 };
-use cw2::set_contract_version;
+use cw2::{get_contract_version, set_contract_version};
+use cw_storage_plus::Bound;
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
 
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
-use crate::state::{CONFIG, ADMIN, USER_REWARDS, RULES, CONTRACTS, USER_LEVELS, get_next_reward_id, get_next_rule_id};
+use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+use crate::state::{
+    CONFIG, ADMIN, PENDING_ADMIN, ROLES, PAUSED, REWARDS, EXPIRY_QUEUE, RULES, CONTRACTS, CONTRACT_ADDRS, USER_LEVELS, TREASURY, STATS,
+    AUTO_CLAIM_OPT_OUTS, PROCESSED_EVENTS, ProcessedEvent, SystemStats,
+    get_next_reward_id, get_next_rule_id, may_load_reward, reward_key, status_key, record_reward_created,
+    record_reward_status_change, track_user, update_stats,
+};
+use crate::{access, exchange, levels, migrations, nft, referral, rules, treasury, vesting};
 
 const CONTRACT_NAME: &str = "luckee-incentive";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+// 分页查询默认与最大返回数量
+const DEFAULT_LIMIT: u32 = 30;
+const MAX_LIMIT: u32 = 100;
+// 子消息回调ID
+const REPLY_POINTS_EXCHANGE_MINT: u64 = 1;
+const REPLY_REWARD_MINT: u64 = 2;
+
+// 奖励历史查询默认与最大返回数量
+const DEFAULT_HISTORY_LIMIT: u32 = 50;
+const MAX_HISTORY_LIMIT: u32 = 1000;
+
 #[entry_point]
 pub fn instantiate(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
@@ -24,8 +44,11 @@ pub fn instantiate(
         .transpose()?
         .unwrap_or_else(|| info.sender.clone());
 
+    validate_config(&msg.config)?;
+
     ADMIN.save(deps.storage, &admin)?;
     CONFIG.save(deps.storage, &msg.config)?;
+    STATS.save(deps.storage, &SystemStats::new(env.block.time))?;
 
     Ok(Response::new()
         .add_attribute("method", "instantiate")
@@ -40,13 +63,42 @@ pub fn execute(
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
+    // 只有 FundTreasury 接受原生代币，其他消息附带的资金会滞留在合约中且不入账
+    if !matches!(msg, ExecuteMsg::FundTreasury {}) {
+        cw_utils::nonpayable(&info)?;
+    }
+
     match msg {
-        ExecuteMsg::DistributeReward { user, amount, activity_type } => {
-            execute_distribute_reward(deps, env, info, user, amount, activity_type)
+        ExecuteMsg::DistributeReward { user, amount, activity_type, event_id } => {
+            execute_distribute_reward(deps, env, info, user, amount, activity_type, event_id)
+        },
+        ExecuteMsg::DistributeNftReward { user, source, activity_type, event_id } => {
+            execute_distribute_nft_reward(deps, env, info, user, source, activity_type, event_id)
+        },
+        ExecuteMsg::DistributeRewards { entries } => {
+            execute_distribute_rewards(deps, env, info, entries)
         },
         ExecuteMsg::ClaimReward { reward_id } => {
             execute_claim_reward(deps, env, info, reward_id)
         },
+        ExecuteMsg::ClaimRewards { reward_ids } => {
+            execute_claim_rewards(deps, env, info, reward_ids)
+        },
+        ExecuteMsg::ClaimAll { limit } => {
+            execute_claim_all(deps, env, info, limit)
+        },
+        ExecuteMsg::ClaimVested { reward_id } => {
+            execute_claim_vested(deps, env, info, reward_id)
+        },
+        ExecuteMsg::CancelReward { reward_id } => {
+            execute_cancel_reward(deps, env, info, reward_id)
+        },
+        ExecuteMsg::ExpireRewards { limit } => {
+            execute_expire_rewards(deps, env, info, limit)
+        },
+        ExecuteMsg::ReportActivity { user, activity_type, value, event_id } => {
+            execute_report_activity(deps, env, info, user, activity_type, value, event_id)
+        },
         ExecuteMsg::MintForPoints { user, points_amount } => {
             execute_mint_for_points(deps, env, info, user, points_amount)
         },
@@ -59,28 +111,148 @@ pub fn execute(
         ExecuteMsg::DeleteRule { rule_id } => {
             execute_delete_rule(deps, env, info, rule_id)
         },
-        ExecuteMsg::RegisterContract { contract_type, contract_addr } => {
-            execute_register_contract(deps, env, info, contract_type, contract_addr)
+        ExecuteMsg::RegisterContract { contract_type, contract_addr, capabilities } => {
+            execute_register_contract(deps, env, info, contract_type, contract_addr, capabilities)
         },
-        ExecuteMsg::UpdateUserLevel { user, points } => {
-            execute_update_user_level(deps, env, info, user, points)
+        ExecuteMsg::UpdateContract { contract_type, status, capabilities } => {
+            execute_update_contract(deps, env, info, contract_type, status, capabilities)
+        },
+        ExecuteMsg::AddPoints { user, points } => {
+            execute_change_points(deps, env, info, user, points, true)
+        },
+        ExecuteMsg::SubtractPoints { user, points } => {
+            execute_change_points(deps, env, info, user, points, false)
         },
         ExecuteMsg::UpdateConfig { config } => {
             execute_update_config(deps, env, info, config)
         },
+        ExecuteMsg::ProposeAdmin { new_admin } => {
+            execute_propose_admin(deps, env, info, new_admin)
+        },
+        ExecuteMsg::AcceptAdmin {} => {
+            execute_accept_admin(deps, env, info)
+        },
+        ExecuteMsg::RenounceAdmin {} => {
+            execute_renounce_admin(deps, env, info)
+        },
+        ExecuteMsg::GrantRole { role, account } => {
+            execute_grant_role(deps, env, info, role, account)
+        },
+        ExecuteMsg::RevokeRole { role, account } => {
+            execute_revoke_role(deps, env, info, role, account)
+        },
+        ExecuteMsg::Pause { scope } => {
+            execute_set_paused(deps, env, info, scope, true)
+        },
+        ExecuteMsg::Unpause { scope } => {
+            execute_set_paused(deps, env, info, scope, false)
+        },
+        ExecuteMsg::SetAutoClaim { enabled } => {
+            execute_set_auto_claim(deps, env, info, enabled)
+        },
+        ExecuteMsg::BindReferrer { referrer } => {
+            execute_bind_referrer(deps, env, info, referrer)
+        },
+        ExecuteMsg::FundTreasury {} => {
+            execute_fund_treasury(deps, env, info)
+        },
+        ExecuteMsg::Receive(wrapper) => {
+            execute_receive(deps, env, info, wrapper)
+        },
+        ExecuteMsg::WithdrawTreasury { asset, amount, recipient } => {
+            execute_withdraw_treasury(deps, env, info, asset, amount, recipient)
+        },
+        ExecuteMsg::ReceiveNft(wrapper) => {
+            execute_receive_nft(deps, env, info, wrapper)
+        },
+        ExecuteMsg::WithdrawNft { collection, token_id, recipient } => {
+            execute_withdraw_nft(deps, env, info, collection, token_id, recipient)
+        },
     }
 }
 
 #[entry_point]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_json_binary(&query_config(deps)?),
         QueryMsg::UserRewards { user } => to_json_binary(&query_user_rewards(deps, user)?),
+        QueryMsg::Reward { reward_id } => to_json_binary(&query_reward(deps, reward_id)?),
+        QueryMsg::RewardHistory { user, status, activity_type, from_time, to_time, start_after, limit } => {
+            to_json_binary(&query_reward_history(
+                deps, user, status, activity_type, from_time, to_time, start_after, limit,
+            )?)
+        }
         QueryMsg::Rules {} => to_json_binary(&query_rules(deps)?),
-        QueryMsg::Contracts {} => to_json_binary(&query_contracts(deps)?),
+        QueryMsg::Contracts { kind, start_after, limit } => {
+            to_json_binary(&query_contracts(deps, kind, start_after, limit)?)
+        }
+        QueryMsg::Contract { contract_type } => to_json_binary(&query_contract(deps, contract_type)?),
+        QueryMsg::ContractByAddress { addr } => to_json_binary(&query_contract_by_address(deps, addr)?),
         QueryMsg::UserLevel { user } => to_json_binary(&query_user_level(deps, user)?),
+        QueryMsg::AutoClaim { user } => to_json_binary(&query_auto_claim(deps, user)?),
+        QueryMsg::EventProcessed { event_id } => to_json_binary(&query_event_processed(deps, event_id)?),
+        QueryMsg::PauseStatus {} => to_json_binary(&query_pause_status(deps)?),
+        QueryMsg::ExchangeQuota { user } => to_json_binary(&query_exchange_quota(deps, env, user)?),
+        QueryMsg::MintStatus {} => to_json_binary(&query_mint_status(deps)?),
+        QueryMsg::VestingStatus { user } => to_json_binary(&query_vesting_status(deps, env, user)?),
+        QueryMsg::NftEscrow { collection, start_after, limit } => {
+            to_json_binary(&query_nft_escrow(deps, collection, start_after, limit)?)
+        },
+        QueryMsg::Admin {} => to_json_binary(&query_admin(deps)?),
+        QueryMsg::RoleMembers { role, start_after, limit } => {
+            to_json_binary(&query_role_members(deps, role, start_after, limit)?)
+        }
         QueryMsg::SystemStats {} => to_json_binary(&query_system_stats(deps)?),
+        QueryMsg::ActivityStats { start_after, limit } => {
+            to_json_binary(&query_activity_stats(deps, start_after, limit)?)
+        },
+        QueryMsg::TreasuryBalance {} => to_json_binary(&query_treasury_balance(deps, env)?),
+        QueryMsg::ReferralTree { user, depth } => to_json_binary(&query_referral_tree(deps, user, depth)?),
+    }
+}
+
+#[entry_point]
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match msg.id {
+        REPLY_POINTS_EXCHANGE_MINT => reply_points_exchange_mint(deps, env, msg),
+        REPLY_REWARD_MINT => reply_reward_mint(msg),
+        id => Err(StdError::generic_err(format!("unknown reply id: {}", id)).into()),
+    }
+}
+
+#[entry_point]
+pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    // 只接受同名合约的升级，拒绝降级
+    let stored = get_contract_version(deps.storage)?;
+    if stored.contract != CONTRACT_NAME {
+        return Err(ContractError::WrongContract { contract: stored.contract });
+    }
+    let from = parse_version(&stored.version)?;
+    let to = parse_version(CONTRACT_VERSION)?;
+    if from > to {
+        return Err(ContractError::CannotDowngrade { from: stored.version, to: CONTRACT_VERSION.to_string() });
+    }
+
+    if let Some(config) = msg.config {
+        validate_config(&config)?;
+        CONFIG.save(deps.storage, &config)?;
     }
+    // 旧版本配置缺少新字段时必须随迁移提供
+    CONFIG.load(deps.storage).map_err(|_| ContractError::InvalidConfiguration {})?;
+
+    let attributes = migrations::run_steps(deps.storage, &env, &from, &to)?;
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "migrate")
+        .add_attribute("from_version", stored.version)
+        .add_attribute("to_version", CONTRACT_VERSION)
+        .add_attributes(attributes))
+}
+
+fn parse_version(version: &str) -> Result<semver::Version, ContractError> {
+    semver::Version::parse(version).map_err(|_| ContractError::InvalidVersion { version: version.to_string() })
 }
 
 // ===== 执行函数 =====
@@ -92,301 +264,1916 @@ fn execute_distribute_reward(
     user: String,
     amount: Uint128,
     activity_type: crate::msg::ActivityType,
+    event_id: Option<String>,
 ) -> Result<Response, ContractError> {
     // 检查权限
-    let admin = ADMIN.load(deps.storage)?;
-    if info.sender != admin {
-        return Err(ContractError::Unauthorized {});
+    access::ensure_role_or_capability(
+        deps.as_ref(),
+        &info.sender,
+        &crate::msg::Role::Distributor,
+        crate::msg::CAPABILITY_DISTRIBUTE_REWARD,
+    )?;
+
+    ensure_not_paused(deps.storage, &crate::msg::PauseScope::Distribution)?;
+
+    if let Some(response) = duplicate_event_response(deps.storage, event_id.as_deref(), "distribute_reward")? {
+        return Ok(response);
     }
 
-    // 创建奖励
-    let reward_id = get_next_reward_id(deps.storage)?;
-    let reward = crate::msg::UserReward {
-        reward_id: reward_id.clone(),
-        user: user.clone(),
-        amount,
-        reward_type: crate::msg::RewardType::Token,
-        activity_type,
-        created_at: env.block.time,
-        claimed_at: None,
-        expires_at: None,
-        status: crate::msg::RewardStatus::Pending,
-    };
+    let (reward, referral_rewards) = distribute_to_user(deps.storage, &env, &user, amount, activity_type)?;
 
-    // 保存奖励
-    let mut user_rewards = USER_REWARDS.load(deps.storage, user.clone()).unwrap_or_default();
-    user_rewards.push(reward);
-    USER_REWARDS.save(deps.storage, user.clone(), &user_rewards)?;
+    let mut created = vec![&reward];
+    created.extend(referral_rewards.iter().map(|(reward, _)| reward));
+    record_event(deps.storage, &env, &info, event_id, &created)?;
+    let messages = payout_msgs(deps.storage, &created)?;
 
-    Ok(Response::new()
+    let mut response = Response::new()
+        .add_submessages(messages)
         .add_attribute("method", "distribute_reward")
         .add_attribute("user", user)
-        .add_attribute("reward_id", reward_id)
-        .add_attribute("amount", amount))
+        .add_attribute("reward_id", reward.reward_id.clone())
+        .add_attribute("amount", reward.amount);
+    if !referral_rewards.is_empty() {
+        response = response.add_attribute("referral_rewards", format_referral_rewards(&referral_rewards));
+    }
+
+    Ok(response)
 }
 
-fn execute_claim_reward(
+fn execute_distribute_nft_reward(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    reward_id: String,
+    user: String,
+    source: crate::msg::NftRewardSource,
+    activity_type: crate::msg::ActivityType,
+    event_id: Option<String>,
 ) -> Result<Response, ContractError> {
-    // 查找奖励
-    let user_rewards = USER_REWARDS.load(deps.storage, info.sender.to_string()).unwrap_or_default();
-    let mut reward_found = false;
-    
-    for mut reward in user_rewards {
-        if reward.reward_id == reward_id {
-            if reward.status == crate::msg::RewardStatus::Pending {
-                reward.status = crate::msg::RewardStatus::Claimed;
-                reward.claimed_at = Some(env.block.time);
-                reward_found = true;
-                break;
-            }
-        }
-    }
+    // 检查权限
+    access::ensure_role_or_capability(
+        deps.as_ref(),
+        &info.sender,
+        &crate::msg::Role::Distributor,
+        crate::msg::CAPABILITY_DISTRIBUTE_REWARD,
+    )?;
 
-    if !reward_found {
-        return Err(ContractError::RewardNotFound {});
+    ensure_not_paused(deps.storage, &crate::msg::PauseScope::Distribution)?;
+
+    if let Some(response) = duplicate_event_response(deps.storage, event_id.as_deref(), "distribute_nft_reward")? {
+        return Ok(response);
     }
 
-    Ok(Response::new()
-        .add_attribute("method", "claim_reward")
-        .add_attribute("reward_id", reward_id)
-        .add_attribute("user", info.sender))
+    let reward = create_nft_reward(deps.storage, &env, user.clone(), &source, activity_type)?;
+    record_event(deps.storage, &env, &info, event_id, &[&reward])?;
+
+    let mut response = Response::new()
+        .add_submessages(payout_msgs(deps.storage, &[&reward])?)
+        .add_attribute("method", "distribute_nft_reward")
+        .add_attribute("user", user)
+        .add_attribute("reward_id", reward.reward_id.clone());
+    if let Some(nft) = reward.nft {
+        response = response.add_attribute("collection", nft.collection).add_attribute("token_id", nft.token_id);
+    }
+    Ok(response)
 }
 
-fn execute_mint_for_points(
+fn execute_distribute_rewards(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    user: String,
-    points_amount: Uint128,
+    entries: Vec<crate::msg::DistributionEntry>,
 ) -> Result<Response, ContractError> {
-    // 检查权限 - 只有管理员可以执行积分兑换
-    let admin = ADMIN.load(deps.storage)?;
-    if info.sender != admin {
-        return Err(ContractError::Unauthorized {});
+    // 检查权限
+    access::ensure_role_or_capability(
+        deps.as_ref(),
+        &info.sender,
+        &crate::msg::Role::Distributor,
+        crate::msg::CAPABILITY_DISTRIBUTE_REWARD,
+    )?;
+
+    ensure_not_paused(deps.storage, &crate::msg::PauseScope::Distribution)?;
+
+    let config = CONFIG.load(deps.storage)?;
+    if entries.is_empty() {
+        return Err(ContractError::EmptyBatch {});
+    }
+    if entries.len() > config.max_batch_size as usize {
+        return Err(ContractError::BatchTooLarge { max: config.max_batch_size });
+    }
+    let mut seen = std::collections::BTreeSet::new();
+    if let Some(entry) = entries.iter().find(|entry| !seen.insert(entry.user.as_str())) {
+        return Err(ContractError::DuplicateUser { user: entry.user.clone() });
     }
 
-    // 检查最小兑换限制
-    if points_amount < Uint128::from(1000u128) {
-        return Err(ContractError::InvalidAmount {});
+    // 任一条目失败都会使整笔交易回滚
+    let mut direct = vec![];
+    let mut referrals = vec![];
+    let mut total_amount = Uint128::zero();
+    for entry in entries {
+        let (reward, referral_rewards) =
+            distribute_to_user(deps.storage, &env, &entry.user, entry.amount, entry.activity_type)?;
+        total_amount += reward.amount;
+        direct.push(reward);
+        referrals.extend(referral_rewards.into_iter().map(|(reward, _)| reward));
     }
 
-    // 创建代币铸造奖励
-    let reward_id = get_next_reward_id(deps.storage)?;
-    let reward = crate::msg::UserReward {
-        reward_id: reward_id.clone(),
-        user: user.clone(),
-        amount: points_amount, // 1:1 兑换比例
-        reward_type: crate::msg::RewardType::Token,
-        activity_type: crate::msg::ActivityType::Custom { activity_id: "points_exchange".to_string() },
-        created_at: env.block.time,
-        claimed_at: None,
-        expires_at: None,
-        status: crate::msg::RewardStatus::Pending,
+    let summary = crate::msg::DistributeRewardsResponse {
+        count: direct.len() as u32,
+        total_amount,
+        first_reward_id: direct.first().map(|reward| reward.reward_id.clone()).unwrap_or_default(),
+        last_reward_id: direct.last().map(|reward| reward.reward_id.clone()).unwrap_or_default(),
     };
-
-    // 保存奖励
-    let mut user_rewards = USER_REWARDS.load(deps.storage, user.clone()).unwrap_or_default();
-    user_rewards.push(reward);
-    USER_REWARDS.save(deps.storage, user.clone(), &user_rewards)?;
+    let payable: Vec<_> = direct.iter().chain(referrals.iter()).collect();
 
     Ok(Response::new()
-        .add_attribute("method", "mint_for_points")
-        .add_attribute("user", user)
-        .add_attribute("points_amount", points_amount)
-        .add_attribute("reward_id", reward_id))
+        .add_submessages(payout_msgs(deps.storage, &payable)?)
+        .add_attribute("method", "distribute_rewards")
+        .add_attribute("count", summary.count.to_string())
+        .add_attribute("total_amount", summary.total_amount)
+        .add_attribute("first_reward_id", summary.first_reward_id.clone())
+        .add_attribute("last_reward_id", summary.last_reward_id.clone())
+        .set_data(to_json_binary(&summary)?))
 }
 
-fn execute_create_rule(
-    deps: DepsMut,
-    env: Env,
-    info: MessageInfo,
-    mut rule: crate::msg::RuleDetails,
-) -> Result<Response, ContractError> {
-    // 检查权限
-    let admin = ADMIN.load(deps.storage)?;
-    if info.sender != admin {
-        return Err(ContractError::Unauthorized {});
+/// 事件已处理过时返回不做任何分发的响应
+fn duplicate_event_response(
+    storage: &dyn Storage,
+    event_id: Option<&str>,
+    method: &str,
+) -> StdResult<Option<Response>> {
+    let Some(event_id) = event_id else {
+        return Ok(None);
+    };
+    Ok(PROCESSED_EVENTS.may_load(storage, event_id.to_string())?.map(|event| {
+        Response::new()
+            .add_attribute("method", method)
+            .add_attribute("event_id", event_id)
+            .add_attribute("duplicate", "true")
+            .add_attribute("reward_ids", event.reward_ids.join(","))
+    }))
+}
+
+fn record_event(
+    storage: &mut dyn Storage,
+    env: &Env,
+    info: &MessageInfo,
+    event_id: Option<String>,
+    rewards: &[&crate::msg::UserReward],
+) -> StdResult<()> {
+    let Some(event_id) = event_id else {
+        return Ok(());
+    };
+    let event = ProcessedEvent {
+        reward_ids: rewards.iter().map(|reward| reward.reward_id.clone()).collect(),
+        processed_by: info.sender.to_string(),
+        processed_at: env.block.time,
+    };
+    PROCESSED_EVENTS.save(storage, event_id, &event)
+}
+
+fn reply_points_exchange_mint(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+    let payload: crate::state::PointsExchangePayload = from_json(&msg.payload)?;
+    let (key, mut reward) =
+        may_load_reward(deps.storage, &payload.reward_id)?.ok_or(ContractError::RewardNotFound {})?;
+    let previous_status = reward.status.clone();
+
+    match msg.result {
+        SubMsgResult::Ok(_) => {
+            reward.status = crate::msg::RewardStatus::Claimed;
+            reward.claimed_at = Some(env.block.time);
+            REWARDS.save(deps.storage, key, &reward)?;
+            record_reward_status_change(deps.storage, &reward, &previous_status, env.block.time)?;
+
+            // 铸币成功后扣除积分，扣除失败会使整笔兑换回滚
+            let withdraw = WasmMsg::Execute {
+                contract_addr: registry_contract_addr(deps.storage)?.to_string(),
+                msg: to_json_binary(&crate::msg::RegistryExecuteMsg::WithdrawPoints {
+                    user: payload.user.clone(),
+                    amount: payload.points,
+                })?,
+                funds: vec![],
+            };
+            Ok(Response::new()
+                .add_message(withdraw)
+                .add_attribute("method", "points_exchange_minted")
+                .add_attribute("reward_id", payload.reward_id)
+                .add_attribute("user", payload.user)
+                .add_attribute("points_amount", payload.points))
+        }
+        SubMsgResult::Err(err) => {
+            reward.status = crate::msg::RewardStatus::Cancelled;
+            REWARDS.save(deps.storage, key, &reward)?;
+            record_reward_status_change(deps.storage, &reward, &previous_status, env.block.time)?;
+            exchange::release(deps.storage, &payload.user, payload.points, reward.created_at)?;
+            treasury::revert_mint(deps.storage, reward.amount)?;
+
+            Ok(Response::new()
+                .add_attribute("method", "points_exchange_failed")
+                .add_attribute("reward_id", payload.reward_id)
+                .add_attribute("user", payload.user)
+                .add_attribute("error", err))
+        }
     }
+}
 
-    // 生成规则ID
-    let rule_id = get_next_rule_id(deps.storage)?;
-    rule.rule_id = rule_id.clone();
-    rule.created_at = env.block.time;
-    rule.updated_at = env.block.time;
+/// 奖励铸币只在失败时回调：返回错误使整笔领取回滚
+fn reply_reward_mint(msg: Reply) -> Result<Response, ContractError> {
+    match msg.result {
+        SubMsgResult::Ok(_) => Ok(Response::new()),
+        SubMsgResult::Err(reason) => {
+            let payload: crate::state::MintPayload = from_json(&msg.payload)?;
+            Err(ContractError::MintFailed {
+                reason: format!("minting {} to {}: {}", payload.amount, payload.recipient, reason),
+            })
+        }
+    }
+}
 
-    // 保存规则
-    RULES.save(deps.storage, rule_id.clone(), &rule)?;
+/// 为用户创建一笔应用等级倍数的代币奖励，并分配推荐奖励
+fn distribute_to_user(
+    storage: &mut dyn Storage,
+    env: &Env,
+    user: &str,
+    amount: Uint128,
+    activity_type: crate::msg::ActivityType,
+) -> Result<(crate::msg::UserReward, Vec<(crate::msg::UserReward, u32)>), ContractError> {
+    // 创建奖励，应用用户等级倍数
+    let multiplier = level_multiplier(storage, user)?;
+    let reward = create_reward(
+        storage,
+        env,
+        user.to_string(),
+        amount,
+        multiplier,
+        crate::msg::RewardType::Token,
+        activity_type,
+        None,
+    )?;
 
-    Ok(Response::new()
-        .add_attribute("method", "create_rule")
-        .add_attribute("rule_id", rule_id))
+    // 分配推荐奖励
+    let referral_rewards = create_referral_rewards(storage, env, user, reward.amount)?;
+    Ok((reward, referral_rewards))
 }
 
-fn execute_update_rule(
+fn execute_claim_reward(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    rule_id: String,
-    mut rule: crate::msg::RuleDetails,
+    reward_id: String,
 ) -> Result<Response, ContractError> {
-    // 检查权限
-    let admin = ADMIN.load(deps.storage)?;
-    if info.sender != admin {
-        return Err(ContractError::Unauthorized {});
-    }
+    ensure_not_paused(deps.storage, &crate::msg::PauseScope::Claims)?;
 
-    // 更新规则
-    rule.rule_id = rule_id.clone();
-    rule.updated_at = env.block.time;
-    RULES.save(deps.storage, rule_id.clone(), &rule)?;
+    let claimed = claim_reward(deps.storage, &env, info.sender.as_str(), &reward_id)?;
 
     Ok(Response::new()
-        .add_attribute("method", "update_rule")
-        .add_attribute("rule_id", rule_id))
+        .add_submessages(payout_msgs(deps.storage, &[&claimed])?)
+        .add_attribute("method", "claim_reward")
+        .add_attribute("reward_id", reward_id)
+        .add_attribute("user", info.sender)
+        .add_attribute("amount", claimed.amount))
 }
 
-fn execute_delete_rule(
+fn execute_claim_rewards(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
-    rule_id: String,
+    reward_ids: Vec<String>,
 ) -> Result<Response, ContractError> {
-    // 检查权限
-    let admin = ADMIN.load(deps.storage)?;
-    if info.sender != admin {
-        return Err(ContractError::Unauthorized {});
+    if reward_ids.len() > MAX_LIMIT as usize {
+        return Err(ContractError::BatchTooLarge { max: MAX_LIMIT });
     }
+    claim_batch(deps, env, info, reward_ids, "claim_rewards")
+}
 
-    // 删除规则
-    RULES.remove(deps.storage, rule_id.clone());
+fn execute_claim_all(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
 
-    Ok(Response::new()
-        .add_attribute("method", "delete_rule")
-        .add_attribute("rule_id", rule_id))
+    // 跳过已过期但尚未清理的奖励和解锁中的奖励，避免占用名额
+    let reward_ids = REWARDS
+        .idx
+        .status
+        .prefix((info.sender.to_string(), status_key(&crate::msg::RewardStatus::Pending)))
+        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        .filter(|item| {
+            item.as_ref().map_or(true, |(_, reward)| {
+                reward.vesting.is_none() && reward.expires_at.is_none_or(|expires_at| env.block.time < expires_at)
+            })
+        })
+        .take(limit)
+        .map(|item| item.map(|(_, reward)| reward.reward_id))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    claim_batch(deps, env, info, reward_ids, "claim_all")
 }
 
-fn execute_register_contract(
+fn execute_claim_vested(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    contract_type: crate::msg::ContractType,
-    contract_addr: String,
+    reward_id: String,
 ) -> Result<Response, ContractError> {
-    // 检查权限
-    let admin = ADMIN.load(deps.storage)?;
-    if info.sender != admin {
-        return Err(ContractError::Unauthorized {});
-    }
+    ensure_not_paused(deps.storage, &crate::msg::PauseScope::Claims)?;
 
-    // 验证地址
-    let addr = deps.api.addr_validate(&contract_addr)?;
+    let (key, mut reward) = may_load_reward(deps.storage, &reward_id)?
+        .filter(|(_, reward)| reward.user == info.sender.as_str())
+        .ok_or(ContractError::RewardNotFound {})?;
+    match reward.status {
+        crate::msg::RewardStatus::Pending => {}
+        crate::msg::RewardStatus::Claimed => return Err(ContractError::RewardAlreadyClaimed {}),
+        crate::msg::RewardStatus::Expired => return Err(ContractError::RewardExpired {}),
+        crate::msg::RewardStatus::Cancelled => return Err(ContractError::OperationNotAllowed {}),
+    }
 
-    // 创建合约信息
-    let contract_info = crate::msg::ContractInfo {
-        contract_type: contract_type.clone(),
-        contract_addr: addr,
-        status: crate::msg::ContractStatus::Active,
-        capabilities: vec![],
-        registered_at: env.block.time,
+    let amount = vesting::releasable(&reward, env.block.time);
+    let Some(tracked) = reward.vesting.as_mut() else {
+        return Err(ContractError::OperationNotAllowed {});
     };
+    if amount.is_zero() {
+        return Err(ContractError::NothingVested {});
+    }
+    tracked.claimed += amount;
+    let claimed = tracked.claimed;
 
-    // 保存合约信息
-    CONTRACTS.save(deps.storage, contract_type.clone(), &contract_info)?;
+    if let Some(asset) = &reward.asset {
+        treasury::settle(deps.storage, asset, amount)?;
+    }
+
+    // 全部领取后奖励记为 Claimed
+    if claimed == reward.amount {
+        reward.status = crate::msg::RewardStatus::Claimed;
+        reward.claimed_at = Some(env.block.time);
+        REWARDS.save(deps.storage, key, &reward)?;
+        record_reward_status_change(deps.storage, &reward, &crate::msg::RewardStatus::Pending, env.block.time)?;
+    } else {
+        REWARDS.save(deps.storage, key, &reward)?;
+    }
+
+    let mint_cap = CONFIG.load(deps.storage)?.mint_cap;
+    let payout = token_payout_msg(deps.storage, mint_cap, &info.sender, amount, reward.asset.clone())?;
 
     Ok(Response::new()
-        .add_attribute("method", "register_contract")
-        .add_attribute("contract_type", format!("{:?}", contract_type)))
+        .add_submessage(payout)
+        .add_attribute("method", "claim_vested")
+        .add_attribute("reward_id", reward_id)
+        .add_attribute("user", info.sender)
+        .add_attribute("amount", amount)
+        .add_attribute("claimed", claimed)
+        .add_attribute("remaining", reward.amount - claimed))
 }
 
-fn execute_update_user_level(
+/// 逐条领取奖励，按资产合并代币发放
+fn claim_batch(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
-    user: String,
-    points: u32,
+    reward_ids: Vec<String>,
+    method: &str,
 ) -> Result<Response, ContractError> {
-    // 检查权限
-    let admin = ADMIN.load(deps.storage)?;
-    if info.sender != admin {
-        return Err(ContractError::Unauthorized {});
-    }
+    ensure_not_paused(deps.storage, &crate::msg::PauseScope::Claims)?;
 
-    // 更新用户等级
-    let mut user_level = USER_LEVELS.load(deps.storage, user.clone()).unwrap_or_else(|_| {
-        crate::msg::UserLevelInfo {
-            user: user.clone(),
-            level: crate::msg::UserLevel::Bronze,
-            points: 0,
-            level_up_count: 0,
-            last_level_up: None,
-            total_rewards: Uint128::zero(),
+    let mut claimed_rewards = vec![];
+    let mut outcomes = vec![];
+    let mut claimed_ids = vec![];
+    let mut failed_ids = vec![];
+
+    // reward_id 由调用者提供，只放在属性值中，属性键保持固定
+    for reward_id in reward_ids {
+        match claim_reward(deps.storage, &env, info.sender.as_str(), &reward_id) {
+            Ok(claimed) => {
+                claimed_rewards.push(claimed);
+                claimed_ids.push(reward_id.clone());
+                outcomes.push(crate::msg::ClaimOutcome { reward_id, claimed: true, error: None });
+            }
+            Err(err) => {
+                failed_ids.push(reward_id.clone());
+                outcomes.push(crate::msg::ClaimOutcome { reward_id, claimed: false, error: Some(err.to_string()) });
+            }
         }
-    });
+    }
 
-    user_level.points = points;
-    USER_LEVELS.save(deps.storage, user.clone(), &user_level)?;
+    let claimed_refs: Vec<_> = claimed_rewards.iter().collect();
+    let payouts = payout_msgs(deps.storage, &claimed_refs)?;
 
+    let claimed_count = outcomes.iter().filter(|outcome| outcome.claimed).count() as u32;
     Ok(Response::new()
-        .add_attribute("method", "update_user_level")
-        .add_attribute("user", user)
-        .add_attribute("points", points.to_string()))
+        .add_submessages(payouts)
+        .add_attribute("method", method)
+        .add_attribute("user", info.sender)
+        .add_attribute("claimed_ids", claimed_ids.join(","))
+        .add_attribute("failed_ids", failed_ids.join(","))
+        .add_attribute("claimed_count", claimed_count.to_string())
+        .set_data(to_json_binary(&crate::msg::ClaimRewardsResponse { outcomes, claimed_count })?))
 }
 
-fn execute_update_config(
-    deps: DepsMut,
-    _env: Env,
-    info: MessageInfo,
-    config: crate::msg::IncentiveConfig,
-) -> Result<Response, ContractError> {
-    // 检查权限
-    let admin = ADMIN.load(deps.storage)?;
-    if info.sender != admin {
-        return Err(ContractError::Unauthorized {});
-        }
-        
-        // 更新配置
-    CONFIG.save(deps.storage, &config)?;
-        
-        Ok(Response::new()
-        .add_attribute("method", "update_config"))
-}
+/// 将用户的一笔待领取奖励标记为已领取，返回领取后的奖励；校验失败时不修改状态
+fn claim_reward(
+    storage: &mut dyn Storage,
+    env: &Env,
+    user: &str,
+    reward_id: &str,
+) -> Result<crate::msg::UserReward, ContractError> {
+    // 查找奖励
+    let (key, mut reward) = may_load_reward(storage, reward_id)?
+        .filter(|(_, reward)| reward.user == user)
+        .ok_or(ContractError::RewardNotFound {})?;
 
-// ===== 查询函数 =====
+    // 检查奖励状态
+    match reward.status {
+        crate::msg::RewardStatus::Pending => {}
+        crate::msg::RewardStatus::Claimed => return Err(ContractError::RewardAlreadyClaimed {}),
+        crate::msg::RewardStatus::Expired => return Err(ContractError::RewardExpired {}),
+        crate::msg::RewardStatus::Cancelled => return Err(ContractError::OperationNotAllowed {}),
+    }
+    if reward.expires_at.is_some_and(|expires_at| env.block.time >= expires_at) {
+        return Err(ContractError::RewardExpired {});
+    }
+    if reward.vesting.is_some() {
+        return Err(ContractError::RewardVesting {});
+    }
 
-fn query_config(deps: Deps) -> StdResult<crate::msg::IncentiveConfig> {
-    CONFIG.load(deps.storage)
-}
+    if let Some(asset) = &reward.asset {
+        treasury::settle(storage, asset, reward.amount)?;
+    }
 
-fn query_user_rewards(deps: Deps, user: String) -> StdResult<Vec<crate::msg::UserReward>> {
-    Ok(USER_REWARDS.load(deps.storage, user.clone()).unwrap_or_default())
-}
+    let previous_status = reward.status.clone();
+    reward.status = crate::msg::RewardStatus::Claimed;
+    reward.claimed_at = Some(env.block.time);
 
-fn query_rules(deps: Deps) -> StdResult<Vec<crate::msg::RuleDetails>> {
-    let rules: Result<Vec<_>, _> = RULES.range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
-        .collect();
-    Ok(rules?.into_iter().map(|(_, rule)| rule).collect())
-}
+    // 先持久化状态，再发出外部调用
+    REWARDS.save(storage, key, &reward)?;
+    record_reward_status_change(storage, &reward, &previous_status, env.block.time)?;
+    if let Some(expires_at) = reward.expires_at {
+        EXPIRY_QUEUE.remove(storage, (expires_at.seconds(), reward.reward_id.clone()));
+    }
 
-fn query_contracts(_deps: Deps) -> StdResult<Vec<crate::msg::ContractInfo>> {
-    // 简化实现：返回空列表，因为ContractType作为key比较复杂
-    Ok(vec![])
+    Ok(reward)
 }
 
-fn query_user_level(deps: Deps, user: String) -> StdResult<crate::msg::UserLevelInfo> {
+fn execute_cancel_reward(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    reward_id: String,
+) -> Result<Response, ContractError> {
+    // 检查权限
+    access::ensure_role(deps.storage, &info.sender, &crate::msg::Role::Distributor)?;
+
+    ensure_not_paused(deps.storage, &crate::msg::PauseScope::All)?;
+
+    let (key, mut reward) = may_load_reward(deps.storage, &reward_id)?.ok_or(ContractError::RewardNotFound {})?;
+    if reward.status != crate::msg::RewardStatus::Pending {
+        return Err(ContractError::OperationNotAllowed {});
+    }
+
+    reward.status = crate::msg::RewardStatus::Cancelled;
+    REWARDS.save(deps.storage, key, &reward)?;
+    if let Some(expires_at) = reward.expires_at {
+        EXPIRY_QUEUE.remove(deps.storage, (expires_at.seconds(), reward.reward_id.clone()));
+    }
+    // 解锁中的奖励只退回尚未领取的部分
+    let claimed = reward.vesting.as_ref().map_or(Uint128::zero(), |vesting| vesting.claimed);
+    if let Some(asset) = &reward.asset {
+        treasury::release(deps.storage, asset, reward.amount - claimed)?;
+    }
+    if let Some(nft) = &reward.nft {
+        nft::release(deps.storage, nft, env.block.time)?;
+    }
+    record_reward_status_change(deps.storage, &reward, &crate::msg::RewardStatus::Pending, env.block.time)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "cancel_reward")
+        .add_attribute("reward_id", reward_id)
+        .add_attribute("user", reward.user)
+        .add_attribute("amount", reward.amount))
+}
+
+fn execute_expire_rewards(
+    deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    // 过期队列按时间排序，只取到当前区块时间为止
+    let due: Vec<_> = EXPIRY_QUEUE
+        .range(
+            deps.storage,
+            None,
+            Some(Bound::exclusive((env.block.time.seconds() + 1, String::new()))),
+            cosmwasm_std::Order::Ascending,
+        )
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut expired_ids = vec![];
+    for ((expires_at, reward_id), _user) in due {
+        EXPIRY_QUEUE.remove(deps.storage, (expires_at, reward_id.clone()));
+
+        let Some((key, mut expired)) = may_load_reward(deps.storage, &reward_id)?
+            .filter(|(_, reward)| reward.status == crate::msg::RewardStatus::Pending)
+        else {
+            continue;
+        };
+        expired.status = crate::msg::RewardStatus::Expired;
+        REWARDS.save(deps.storage, key, &expired)?;
+
+        // 未领取的负债退回金库
+        if let Some(asset) = &expired.asset {
+            treasury::release(deps.storage, asset, expired.amount)?;
+        }
+        if let Some(nft) = &expired.nft {
+            nft::release(deps.storage, nft, env.block.time)?;
+        }
+        record_reward_status_change(deps.storage, &expired, &crate::msg::RewardStatus::Pending, env.block.time)?;
+        expired_ids.push(reward_id);
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", "expire_rewards")
+        .add_attribute("expired_count", expired_ids.len().to_string())
+        .add_attribute("reward_ids", expired_ids.join(",")))
+}
+
+fn execute_report_activity(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    user: String,
+    activity_type: crate::msg::ActivityType,
+    value: Uint128,
+    event_id: Option<String>,
+) -> Result<Response, ContractError> {
+    // 检查权限
+    access::ensure_role_or_capability(
+        deps.as_ref(),
+        &info.sender,
+        &crate::msg::Role::Distributor,
+        crate::msg::CAPABILITY_DISTRIBUTE_REWARD,
+    )?;
+
+    ensure_not_paused(deps.storage, &crate::msg::PauseScope::Distribution)?;
+
+    if let Some(response) = duplicate_event_response(deps.storage, event_id.as_deref(), "report_activity")? {
+        return Ok(response);
+    }
+
+    let user_level = USER_LEVELS
+        .may_load(deps.storage, user.clone())?
+        .map(|info| info.level)
+        .unwrap_or(crate::msg::UserLevel::Bronze);
+    let ctx = rules::ActivityContext {
+        activity_type: &activity_type,
+        user_level: &user_level,
+        value,
+        now: env.block.time,
+    };
+
+    // 收集所有匹配规则产生的奖励
+    let matched: Vec<_> = RULES
+        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?
+        .into_iter()
+        .filter(|(_, rule)| rules::rule_matches(rule, &ctx))
+        .collect();
+
+    let mut created = vec![];
+    let mut total_amount = Uint128::zero();
+    let mut referral_rewards = vec![];
+    let multiplier = level_multiplier(deps.storage, &user)?;
+    for (_, rule) in &matched {
+        for definition in &rule.rewards {
+            if let (crate::msg::RewardType::Nft, Some(source)) = (&definition.reward_type, &definition.nft) {
+                let reward = create_nft_reward(deps.storage, &env, user.clone(), source, activity_type.clone())?;
+                created.push(reward);
+                continue;
+            }
+            let amount = rules::reward_amount(definition);
+            if amount.mul_floor(multiplier).is_zero() {
+                continue;
+            }
+            let reward = create_reward(
+                deps.storage,
+                &env,
+                user.clone(),
+                amount,
+                multiplier,
+                definition.reward_type.clone(),
+                activity_type.clone(),
+                definition.vesting.clone(),
+            )?;
+            if reward.reward_type == crate::msg::RewardType::Token {
+                referral_rewards.extend(create_referral_rewards(deps.storage, &env, &user, reward.amount)?);
+            }
+            total_amount += reward.amount;
+            created.push(reward);
+        }
+    }
+
+    let reward_ids: Vec<_> = created.iter().map(|reward| reward.reward_id.as_str()).collect();
+    let mut payable: Vec<_> = created.iter().collect();
+    payable.extend(referral_rewards.iter().map(|(reward, _)| reward));
+    record_event(deps.storage, &env, &info, event_id, &payable)?;
+
+    let mut response = Response::new()
+        .add_submessages(payout_msgs(deps.storage, &payable)?)
+        .add_attribute("method", "report_activity")
+        .add_attribute("user", user.clone())
+        .add_attribute("activity_type", rules::activity_type_name(&activity_type))
+        .add_attribute("rules_matched", matched.len().to_string())
+        .add_attribute("reward_ids", reward_ids.join(","))
+        .add_attribute("total_amount", total_amount);
+    if !referral_rewards.is_empty() {
+        response = response.add_attribute("referral_rewards", format_referral_rewards(&referral_rewards));
+    }
+
+    Ok(response)
+}
+
+fn execute_mint_for_points(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    user: String,
+    points_amount: Uint128,
+) -> Result<Response, ContractError> {
+    // 检查权限 - 管理员、Distributor 或拥有铸造权限的注册合约可以执行积分兑换
+    access::ensure_role_or_capability(
+        deps.as_ref(),
+        &info.sender,
+        &crate::msg::Role::Distributor,
+        crate::msg::CAPABILITY_MINT_TOKENS,
+    )?;
+
+    ensure_not_paused(deps.storage, &crate::msg::PauseScope::PointsExchange)?;
+    let registry = registry_contract_addr(deps.storage)?;
+
+    // 检查单次上下限与 24 小时额度
+    let crate::msg::IncentiveConfig { points_exchange: config, mint_cap, .. } = CONFIG.load(deps.storage)?;
+    exchange::consume(deps.storage, &config, &user, points_amount, env.block.time)?;
+    let token_amount = exchange::tokens_for(&config, points_amount);
+    if token_amount.is_zero() {
+        return Err(ContractError::InvalidAmount {});
+    }
+    treasury::record_mint(deps.storage, mint_cap, token_amount)?;
+
+    // 从 DD Registry 查询可用积分
+    let balance: crate::msg::UserPointsResponse = deps.querier.query_wasm_smart(
+        registry,
+        &crate::msg::RegistryQueryMsg::UserPoints { user: user.clone() },
+    )?;
+    if balance.points < points_amount {
+        return Err(ContractError::InsufficientPoints {});
+    }
+
+    // 兑换记录不占用金库，铸币完成前保持 Pending；倍数记录兑换比例
+    let reward = crate::msg::UserReward {
+        reward_id: get_next_reward_id(deps.storage)?,
+        user: user.clone(),
+        amount: token_amount,
+        base_amount: points_amount,
+        multiplier: config.rate,
+        reward_type: crate::msg::RewardType::Token,
+        activity_type: crate::msg::ActivityType::Custom { activity_id: "points_exchange".to_string() },
+        created_at: env.block.time,
+        claimed_at: None,
+        expires_at: None,
+        status: crate::msg::RewardStatus::Pending,
+        asset: None,
+        nft: None,
+        vesting: None,
+    };
+    let key = reward_key(&reward.reward_id).ok_or(ContractError::RewardNotFound {})?;
+    REWARDS.save(deps.storage, key, &reward)?;
+    record_reward_created(deps.storage, &reward, env.block.time)?;
+
+    // 先铸币，成功后在回调中扣除积分；铸币失败时作废兑换记录且不扣积分
+    let mint = WasmMsg::Execute {
+        contract_addr: ft_contract_addr(deps.storage)?.to_string(),
+        msg: to_json_binary(&Cw20ExecuteMsg::Mint { recipient: user.clone(), amount: reward.amount })?,
+        funds: vec![],
+    };
+    let payload = crate::state::PointsExchangePayload {
+        reward_id: reward.reward_id.clone(),
+        user: user.clone(),
+        points: points_amount,
+    };
+
+    Ok(Response::new()
+        .add_submessage(SubMsg::reply_always(mint, REPLY_POINTS_EXCHANGE_MINT).with_payload(to_json_binary(&payload)?))
+        .add_attribute("method", "mint_for_points")
+        .add_attribute("user", user)
+        .add_attribute("points_amount", points_amount)
+        .add_attribute("token_amount", token_amount)
+        .add_attribute("reward_id", reward.reward_id))
+}
+
+fn execute_create_rule(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    mut rule: crate::msg::RuleDetails,
+) -> Result<Response, ContractError> {
+    // 检查权限
+    access::ensure_role(deps.storage, &info.sender, &crate::msg::Role::RuleManager)?;
+
+    ensure_not_paused(deps.storage, &crate::msg::PauseScope::RuleChanges)?;
+
+    rules::validate_rule(&rule)?;
+
+    // 生成规则ID
+    let rule_id = get_next_rule_id(deps.storage)?;
+    rule.rule_id = rule_id.clone();
+    rule.created_at = env.block.time;
+    rule.updated_at = env.block.time;
+
+    // 保存规则
+    RULES.save(deps.storage, rule_id.clone(), &rule)?;
+    update_stats(deps.storage, env.block.time, |stats| stats.total_rules += 1)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "create_rule")
+        .add_attribute("rule_id", rule_id))
+}
+
+fn execute_update_rule(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    rule_id: String,
+    mut rule: crate::msg::RuleDetails,
+) -> Result<Response, ContractError> {
+    // 检查权限
+    access::ensure_role(deps.storage, &info.sender, &crate::msg::Role::RuleManager)?;
+
+    ensure_not_paused(deps.storage, &crate::msg::PauseScope::RuleChanges)?;
+
+    rules::validate_rule(&rule)?;
+
+    if !RULES.has(deps.storage, rule_id.clone()) {
+        return Err(ContractError::RuleNotFound {});
+    }
+
+    // 更新规则
+    rule.rule_id = rule_id.clone();
+    rule.updated_at = env.block.time;
+    RULES.save(deps.storage, rule_id.clone(), &rule)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "update_rule")
+        .add_attribute("rule_id", rule_id))
+}
+
+fn execute_delete_rule(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    rule_id: String,
+) -> Result<Response, ContractError> {
+    // 检查权限
+    access::ensure_role(deps.storage, &info.sender, &crate::msg::Role::RuleManager)?;
+
+    ensure_not_paused(deps.storage, &crate::msg::PauseScope::RuleChanges)?;
+
+    if !RULES.has(deps.storage, rule_id.clone()) {
+        return Err(ContractError::RuleNotFound {});
+    }
+
+    // 删除规则
+    RULES.remove(deps.storage, rule_id.clone());
+    update_stats(deps.storage, env.block.time, |stats| stats.total_rules -= 1)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "delete_rule")
+        .add_attribute("rule_id", rule_id))
+}
+
+fn execute_register_contract(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    contract_type: crate::msg::ContractType,
+    contract_addr: String,
+    capabilities: Vec<String>,
+) -> Result<Response, ContractError> {
+    // 检查权限
+    access::ensure_admin(deps.storage, &info.sender)?;
+
+    // 验证地址与权限
+    let addr = deps.api.addr_validate(&contract_addr)?;
+    validate_capabilities(&capabilities)?;
+
+    // 同一地址只能注册为一种合约类型
+    if let Some(existing) = CONTRACT_ADDRS.may_load(deps.storage, addr.to_string())? {
+        if existing != contract_type {
+            return Err(ContractError::ContractAlreadyRegistered {});
+        }
+    }
+
+    // 重新注册时移除旧地址的索引
+    match CONTRACTS.may_load(deps.storage, contract_type.clone())? {
+        Some(previous) => CONTRACT_ADDRS.remove(deps.storage, previous.contract_addr.to_string()),
+        None => {
+            update_stats(deps.storage, env.block.time, |stats| stats.total_contracts += 1)?;
+        }
+    }
+
+    // 创建合约信息
+    let contract_info = crate::msg::ContractInfo {
+        contract_type: contract_type.clone(),
+        contract_addr: addr.clone(),
+        status: crate::msg::ContractStatus::Active,
+        capabilities,
+        registered_at: env.block.time,
+    };
+
+    // 保存合约信息
+    CONTRACTS.save(deps.storage, contract_type.clone(), &contract_info)?;
+    CONTRACT_ADDRS.save(deps.storage, addr.to_string(), &contract_type)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "register_contract")
+        .add_attribute("contract_type", format!("{:?}", contract_type))
+        .add_attribute("contract_addr", addr))
+}
+
+fn execute_update_contract(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    contract_type: crate::msg::ContractType,
+    status: Option<crate::msg::ContractStatus>,
+    capabilities: Option<Vec<String>>,
+) -> Result<Response, ContractError> {
+    // 检查权限
+    access::ensure_admin(deps.storage, &info.sender)?;
+
+    let mut contract_info = CONTRACTS
+        .may_load(deps.storage, contract_type.clone())?
+        .ok_or(ContractError::ContractNotFound {})?;
+
+    if let Some(status) = status {
+        contract_info.status = status;
+    }
+    if let Some(capabilities) = capabilities {
+        validate_capabilities(&capabilities)?;
+        contract_info.capabilities = capabilities;
+    }
+    CONTRACTS.save(deps.storage, contract_type.clone(), &contract_info)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "update_contract")
+        .add_attribute("contract_type", format!("{:?}", contract_type))
+        .add_attribute("status", format!("{:?}", contract_info.status))
+        .add_attribute("capabilities", contract_info.capabilities.join(",")))
+}
+
+fn execute_change_points(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    user: String,
+    points: u32,
+    increase: bool,
+) -> Result<Response, ContractError> {
+    // 检查权限
+    access::ensure_role(deps.storage, &info.sender, &crate::msg::Role::LevelOperator)?;
+
+    let config = CONFIG.load(deps.storage)?;
+    let mut user_level = USER_LEVELS.may_load(deps.storage, user.clone())?.unwrap_or_else(|| {
+        crate::msg::UserLevelInfo {
+            user: user.clone(),
+            level: crate::msg::UserLevel::Bronze,
+            points: 0,
+            level_up_count: 0,
+            last_level_up: None,
+            total_rewards: Uint128::zero(),
+        }
+    });
+
+    // 按增量更新积分
+    user_level.points = if increase {
+        user_level.points.checked_add(points).ok_or(ContractError::InvalidAmount {})?
+    } else {
+        user_level.points.checked_sub(points).ok_or(ContractError::InsufficientPoints {})?
+    };
+
+    // 根据阈值自动升级（可选降级）
+    let previous_level = user_level.level.clone();
+    let target_level = levels::level_for_points(&config.level_thresholds, user_level.points);
+    let promoted = target_level.rank() > previous_level.rank();
+    if promoted {
+        user_level.level_up_count += u32::from(target_level.rank() - previous_level.rank());
+        user_level.last_level_up = Some(env.block.time);
+        user_level.level = target_level;
+    } else if config.level_demotion_enabled && target_level.rank() < previous_level.rank() {
+        user_level.level = target_level;
+    }
+    USER_LEVELS.save(deps.storage, user.clone(), &user_level)?;
+    track_user(deps.storage, &user, env.block.time)?;
+
+    let mut response = Response::new()
+        .add_attribute("method", if increase { "add_points" } else { "subtract_points" })
+        .add_attribute("user", user.clone())
+        .add_attribute("points", points.to_string())
+        .add_attribute("total_points", user_level.points.to_string())
+        .add_attribute("level", format!("{:?}", user_level.level));
+
+    // 升级奖励：无法发放时跳过奖励，积分与等级照常保存
+    if promoted && !config.level_up_reward.is_zero() {
+        if let Some(reason) = level_up_reward_blocked(deps.storage, &config, &user, config.level_up_reward)? {
+            return Ok(response.add_attribute("level_up_reward_skipped", reason));
+        }
+        let reward = create_reward(
+            deps.storage,
+            &env,
+            user,
+            config.level_up_reward,
+            Decimal::one(),
+            crate::msg::RewardType::Token,
+            crate::msg::ActivityType::LevelUp { new_level: user_level.level.clone() },
+            None,
+        )?;
+        response = response
+            .add_submessages(payout_msgs(deps.storage, &[&reward])?)
+            .add_attribute("level_up_reward_id", reward.reward_id);
+    }
+
+    Ok(response)
+}
+
+fn execute_propose_admin(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    new_admin: String,
+) -> Result<Response, ContractError> {
+    // 检查权限
+    access::ensure_admin(deps.storage, &info.sender)?;
+
+    let new_admin = deps.api.addr_validate(&new_admin)?;
+    PENDING_ADMIN.save(deps.storage, &new_admin)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "propose_admin")
+        .add_attribute("pending_admin", new_admin))
+}
+
+fn execute_accept_admin(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    // 只有被提名的地址可以接受
+    if PENDING_ADMIN.may_load(deps.storage)?.as_ref() != Some(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    ADMIN.save(deps.storage, &info.sender)?;
+    PENDING_ADMIN.remove(deps.storage);
+
+    Ok(Response::new()
+        .add_attribute("method", "accept_admin")
+        .add_attribute("admin", info.sender))
+}
+
+fn execute_renounce_admin(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    // 检查权限
+    access::ensure_admin(deps.storage, &info.sender)?;
+
+    ADMIN.remove(deps.storage);
+    PENDING_ADMIN.remove(deps.storage);
+
+    Ok(Response::new()
+        .add_attribute("method", "renounce_admin")
+        .add_attribute("admin", info.sender))
+}
+
+fn execute_grant_role(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    role: crate::msg::Role,
+    account: String,
+) -> Result<Response, ContractError> {
+    // 检查权限
+    access::ensure_admin(deps.storage, &info.sender)?;
+
+    let account = deps.api.addr_validate(&account)?;
+    ROLES.save(deps.storage, (role.key().to_string(), account.to_string()), &env.block.time)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "grant_role")
+        .add_attribute("role", role.key())
+        .add_attribute("account", account))
+}
+
+fn execute_revoke_role(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    role: crate::msg::Role,
+    account: String,
+) -> Result<Response, ContractError> {
+    // 检查权限
+    access::ensure_admin(deps.storage, &info.sender)?;
+
+    ROLES.remove(deps.storage, (role.key().to_string(), account.clone()));
+
+    Ok(Response::new()
+        .add_attribute("method", "revoke_role")
+        .add_attribute("role", role.key())
+        .add_attribute("account", account))
+}
+
+fn execute_set_paused(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    scope: crate::msg::PauseScope,
+    paused: bool,
+) -> Result<Response, ContractError> {
+    // 检查权限
+    access::ensure_role(deps.storage, &info.sender, &crate::msg::Role::Pauser)?;
+
+    if paused {
+        let record = crate::msg::PausedScope { scope: scope.clone(), paused_at: env.block.time };
+        PAUSED.save(deps.storage, scope.key(), &record)?;
+    } else {
+        PAUSED.remove(deps.storage, scope.key());
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", if paused { "pause" } else { "unpause" })
+        .add_attribute("scope", scope.key()))
+}
+
+/// 指定范围或全部操作被暂停时拒绝执行
+fn ensure_not_paused(storage: &dyn Storage, scope: &crate::msg::PauseScope) -> Result<(), ContractError> {
+    for scope in [&crate::msg::PauseScope::All, scope] {
+        if PAUSED.has(storage, scope.key()) {
+            return Err(ContractError::Paused { scope: scope.key().to_string() });
+        }
+    }
+    Ok(())
+}
+
+fn execute_set_auto_claim(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    enabled: bool,
+) -> Result<Response, ContractError> {
+    let user = info.sender.to_string();
+    if enabled {
+        AUTO_CLAIM_OPT_OUTS.remove(deps.storage, user.clone());
+    } else {
+        AUTO_CLAIM_OPT_OUTS.save(deps.storage, user.clone(), &env.block.time)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", "set_auto_claim")
+        .add_attribute("user", user)
+        .add_attribute("enabled", enabled.to_string()))
+}
+
+fn execute_update_config(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    config: crate::msg::IncentiveConfig,
+) -> Result<Response, ContractError> {
+    // 检查权限
+    access::ensure_admin(deps.storage, &info.sender)?;
+
+    validate_config(&config)?;
+
+    // 更新配置
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "update_config"))
+}
+
+fn execute_bind_referrer(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    referrer: String,
+) -> Result<Response, ContractError> {
+    let referrer = deps.api.addr_validate(&referrer)?;
+    referral::bind(deps.storage, info.sender.as_str(), referrer.as_str(), env.block.time)?;
+    track_user(deps.storage, info.sender.as_str(), env.block.time)?;
+    track_user(deps.storage, referrer.as_str(), env.block.time)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "bind_referrer")
+        .add_attribute("user", info.sender)
+        .add_attribute("referrer", referrer))
+}
+
+fn execute_fund_treasury(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    if info.funds.is_empty() {
+        return Err(ContractError::InvalidAmount {});
+    }
+
+    let mut response = Response::new()
+        .add_attribute("method", "fund_treasury")
+        .add_attribute("sender", info.sender.clone());
+
+    for coin in info.funds {
+        if coin.amount.is_zero() {
+            return Err(ContractError::InvalidAmount {});
+        }
+        let asset = crate::msg::AssetInfo::Native { denom: coin.denom.clone() };
+        let balance = treasury::deposit(deps.storage, &asset, coin.amount)?;
+        response = response
+            .add_attribute("funded", coin.to_string())
+            .add_attribute("available", format!("{}{}", balance.available, coin.denom));
+    }
+
+    Ok(response)
+}
+
+fn execute_receive(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    wrapper: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    match from_json(&wrapper.msg)? {
+        crate::msg::ReceiveMsg::FundTreasury {} => {
+            if wrapper.amount.is_zero() {
+                return Err(ContractError::InvalidAmount {});
+            }
+
+            // 只接受注册的 Ft 合约转入，其他合约可以不转账直接调用 Receive 伪造入库记录
+            let ft = CONTRACTS.may_load(deps.storage, crate::msg::ContractType::Ft)?;
+            if ft.is_none_or(|ft| ft.contract_addr != info.sender) {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            // 发送此消息的 CW20 合约即为入库资产
+            let asset = crate::msg::AssetInfo::Cw20 { contract_addr: info.sender.clone() };
+            let balance = treasury::deposit(deps.storage, &asset, wrapper.amount)?;
+
+            Ok(Response::new()
+                .add_attribute("method", "fund_treasury")
+                .add_attribute("sender", wrapper.sender)
+                .add_attribute("token", info.sender)
+                .add_attribute("funded", wrapper.amount)
+                .add_attribute("available", balance.available))
+        }
+    }
+}
+
+fn execute_withdraw_treasury(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    asset: crate::msg::AssetInfo,
+    amount: Uint128,
+    recipient: Option<String>,
+) -> Result<Response, ContractError> {
+    // 检查权限
+    access::ensure_role(deps.storage, &info.sender, &crate::msg::Role::Treasurer)?;
+
+    ensure_not_paused(deps.storage, &crate::msg::PauseScope::All)?;
+
+    if amount.is_zero() {
+        return Err(ContractError::InvalidAmount {});
+    }
+
+    // 只能提取未分配给奖励的部分
+    let recipient = recipient
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?
+        .unwrap_or(info.sender);
+    let balance = treasury::withdraw(deps.storage, &asset, amount)?;
+    let msg = build_payout_msg(deps.storage, &recipient, amount, Some(asset.clone()))?;
+
+    Ok(Response::new()
+        .add_message(msg)
+        .add_attribute("method", "withdraw_treasury")
+        .add_attribute("asset", asset.key())
+        .add_attribute("amount", amount)
+        .add_attribute("recipient", recipient)
+        .add_attribute("available", balance.available))
+}
+
+fn execute_receive_nft(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    wrapper: crate::msg::Cw721ReceiveMsg,
+) -> Result<Response, ContractError> {
+    match from_json(&wrapper.msg)? {
+        crate::msg::ReceiveNftMsg::DepositEscrow {} => {
+            // 发送此消息的 CW721 合约即为托管 NFT 所属合约
+            nft::deposit(deps.storage, &info.sender, &wrapper.token_id, env.block.time)?;
+
+            Ok(Response::new()
+                .add_attribute("method", "deposit_nft")
+                .add_attribute("sender", wrapper.sender)
+                .add_attribute("collection", info.sender)
+                .add_attribute("token_id", wrapper.token_id))
+        }
+    }
+}
+
+fn execute_withdraw_nft(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    collection: String,
+    token_id: String,
+    recipient: Option<String>,
+) -> Result<Response, ContractError> {
+    // 检查权限
+    access::ensure_role(deps.storage, &info.sender, &crate::msg::Role::Treasurer)?;
+
+    ensure_not_paused(deps.storage, &crate::msg::PauseScope::All)?;
+
+    // 已分配给奖励的 NFT 不在托管列表中，无法取回
+    let recipient = recipient
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?
+        .unwrap_or(info.sender);
+    nft::withdraw(deps.storage, &collection, &token_id)?;
+    let escrowed = crate::msg::NftReward {
+        collection: Addr::unchecked(&collection),
+        token_id: token_id.clone(),
+        delivery: crate::msg::NftDelivery::Transfer,
+    };
+
+    Ok(Response::new()
+        .add_message(nft::payout_msg(&escrowed, recipient.as_str())?)
+        .add_attribute("method", "withdraw_nft")
+        .add_attribute("collection", collection)
+        .add_attribute("token_id", token_id)
+        .add_attribute("recipient", recipient))
+}
+
+// ===== 内部辅助函数 =====
+
+/// 注册合约的权限只能取 `CAPABILITIES` 中列出的名称
+fn validate_capabilities(capabilities: &[String]) -> Result<(), ContractError> {
+    if capabilities.iter().any(|c| !crate::msg::CAPABILITIES.contains(&c.as_str())) {
+        return Err(ContractError::InvalidConfiguration {});
+    }
+    Ok(())
+}
+
+/// 创建一条待领取奖励，金额为 base_amount * multiplier；代币奖励会从金库锁定对应资产
+#[allow(clippy::too_many_arguments)]
+fn create_reward(
+    storage: &mut dyn Storage,
+    env: &Env,
+    user: String,
+    base_amount: Uint128,
+    multiplier: Decimal,
+    reward_type: crate::msg::RewardType,
+    activity_type: crate::msg::ActivityType,
+    vesting: Option<crate::msg::VestingSchedule>,
+) -> Result<crate::msg::UserReward, ContractError> {
+    let config = CONFIG.load(storage)?;
+    let amount = base_amount.mul_floor(multiplier);
+
+    // 限制每个用户的待领取奖励数量
+    if pending_limit_reached(storage, &config, &user) {
+        return Err(ContractError::RewardLimitExceeded {});
+    }
+    let asset = if reward_type == crate::msg::RewardType::Token {
+        reward_asset(storage, &config.payout_method)?
+    } else {
+        None
+    };
+
+    // 金库余额不足时拒绝分发
+    if let Some(asset) = &asset {
+        treasury::commit(storage, asset, amount)?;
+    }
+
+    // reward_expiration_days 为 0 表示永不过期；解锁中的奖励不会过期
+    let expires_at = (config.reward_expiration_days > 0 && vesting.is_none())
+        .then(|| env.block.time.plus_days(config.reward_expiration_days));
+
+    let reward = crate::msg::UserReward {
+        reward_id: get_next_reward_id(storage)?,
+        user: user.clone(),
+        amount,
+        base_amount,
+        multiplier,
+        reward_type,
+        activity_type,
+        created_at: env.block.time,
+        claimed_at: None,
+        expires_at,
+        status: crate::msg::RewardStatus::Pending,
+        asset,
+        nft: None,
+        vesting: vesting.map(|schedule| crate::msg::RewardVesting { schedule, claimed: Uint128::zero() }),
+    };
+    if let Some(expires_at) = expires_at {
+        EXPIRY_QUEUE.save(storage, (expires_at.seconds(), reward.reward_id.clone()), &user)?;
+    }
+
+    let key = reward_key(&reward.reward_id).ok_or(ContractError::RewardNotFound {})?;
+    REWARDS.save(storage, key, &reward)?;
+    record_reward_created(storage, &reward, env.block.time)?;
+
+    // 自动领取：立即结算，由调用方在同一响应中发放；解锁中的奖励只能分期领取
+    if reward.vesting.is_none() && auto_claim_applies(storage, &config, &user, &reward.reward_type)? {
+        return claim_reward(storage, env, &user, &reward.reward_id);
+    }
+
+    Ok(reward)
+}
+
+/// 创建一笔 NFT 奖励；token_id 依赖奖励编号，因此在奖励创建后确定 NFT 并补写
+fn create_nft_reward(
+    storage: &mut dyn Storage,
+    env: &Env,
+    user: String,
+    source: &crate::msg::NftRewardSource,
+    activity_type: crate::msg::ActivityType,
+) -> Result<crate::msg::UserReward, ContractError> {
+    let mut reward = create_reward(
+        storage,
+        env,
+        user,
+        Uint128::one(),
+        Decimal::one(),
+        crate::msg::RewardType::Nft,
+        activity_type,
+        None,
+    )?;
+    reward.nft = Some(nft::resolve(storage, source, &reward.reward_id, &reward.user)?);
+
+    let key = reward_key(&reward.reward_id).ok_or(ContractError::RewardNotFound {})?;
+    REWARDS.save(storage, key, &reward)?;
+    Ok(reward)
+}
+
+/// 升级奖励无法发放的原因；需在创建奖励前检查，避免奖励失败导致积分更新回滚
+fn level_up_reward_blocked(
+    storage: &dyn Storage,
+    config: &crate::msg::IncentiveConfig,
+    user: &str,
+    amount: Uint128,
+) -> Result<Option<&'static str>, ContractError> {
+    if ensure_not_paused(storage, &crate::msg::PauseScope::Distribution).is_err() {
+        return Ok(Some("paused"));
+    }
+    if pending_limit_reached(storage, config, user) {
+        return Ok(Some("reward_limit"));
+    }
+    let reason = match reward_asset(storage, &config.payout_method)? {
+        Some(asset) => (treasury::available(storage, &asset)? < amount).then_some("insufficient_treasury"),
+        // 铸造模式只有自动领取时才会立即占用铸造额度
+        None => {
+            let exceeded = treasury::mint_status(storage, config.mint_cap)?
+                .remaining
+                .is_some_and(|remaining| remaining < amount);
+            (exceeded && auto_claim_applies(storage, config, user, &crate::msg::RewardType::Token)?)
+                .then_some("mint_cap")
+        }
+    };
+    Ok(reason)
+}
+
+/// 用户的待领取奖励是否已达上限，`max_rewards_per_user` 为 0 表示不限制
+fn pending_limit_reached(storage: &dyn Storage, config: &crate::msg::IncentiveConfig, user: &str) -> bool {
+    if config.max_rewards_per_user == 0 {
+        return false;
+    }
+    let pending = REWARDS
+        .idx
+        .status
+        .prefix((user.to_string(), status_key(&crate::msg::RewardStatus::Pending)))
+        .keys_raw(storage, None, None, cosmwasm_std::Order::Ascending)
+        .take(config.max_rewards_per_user as usize)
+        .count();
+    pending >= config.max_rewards_per_user as usize
+}
+
+/// 用户当前等级对应的奖励倍数
+fn level_multiplier(storage: &dyn Storage, user: &str) -> Result<Decimal, ContractError> {
+    let config = CONFIG.load(storage)?;
+    let level = USER_LEVELS
+        .may_load(storage, user.to_string())?
+        .map(|info| info.level)
+        .unwrap_or(crate::msg::UserLevel::Bronze);
+    Ok(levels::multiplier_for(&config.level_multipliers, &level))
+}
+
+/// 全局开启自动领取、奖励类型不要求手动领取且用户未关闭自动领取时，奖励分发即发放。
+/// 领取暂停期间奖励保持待领取，不会经由自动领取转出。
+fn auto_claim_applies(
+    storage: &dyn Storage,
+    config: &crate::msg::IncentiveConfig,
+    user: &str,
+    reward_type: &crate::msg::RewardType,
+) -> StdResult<bool> {
+    Ok(config.auto_claim_enabled
+        && !config.manual_claim_reward_types.contains(reward_type)
+        && !AUTO_CLAIM_OPT_OUTS.has(storage, user.to_string())
+        && ensure_not_paused(storage, &crate::msg::PauseScope::Claims).is_ok())
+}
+
+/// 按配置的各层比例为用户的上线创建推荐奖励
+fn create_referral_rewards(
+    storage: &mut dyn Storage,
+    env: &Env,
+    user: &str,
+    amount: Uint128,
+) -> Result<Vec<(crate::msg::UserReward, u32)>, ContractError> {
+    let config = CONFIG.load(storage)?;
+    let upline = referral::upline(storage, user, config.referral_rates.len() as u32)?;
+
+    let mut rewards = vec![];
+    for (node, rate) in upline.into_iter().zip(config.referral_rates.iter()) {
+        // 上线的待领取奖励已满时跳过，不影响下线本身的奖励
+        let referral_amount = amount.mul_floor(*rate);
+        if referral_amount.is_zero() || pending_limit_reached(storage, &config, &node.user) {
+            continue;
+        }
+        let reward = create_reward(
+            storage,
+            env,
+            node.user,
+            referral_amount,
+            Decimal::one(),
+            crate::msg::RewardType::Token,
+            crate::msg::ActivityType::Referral { referrer: user.to_string() },
+            None,
+        )?;
+        rewards.push((reward, node.depth));
+    }
+    Ok(rewards)
+}
+
+/// 推荐奖励响应属性："用户:金额:层级"，多条以逗号分隔
+fn format_referral_rewards(rewards: &[(crate::msg::UserReward, u32)]) -> String {
+    rewards
+        .iter()
+        .map(|(reward, depth)| format!("{}:{}:{}", reward.user, reward.amount, depth))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn validate_config(config: &crate::msg::IncentiveConfig) -> Result<(), ContractError> {
+    if config.referral_rates.len() > referral::MAX_REFERRAL_DEPTH as usize
+        || config.referral_rates.iter().any(|rate| *rate > Decimal::one())
+    {
+        return Err(ContractError::InvalidConfiguration {});
+    }
+    levels::validate_thresholds(&config.level_thresholds)?;
+    levels::validate_multipliers(&config.level_multipliers)?;
+    if config.max_batch_size == 0 {
+        return Err(ContractError::InvalidConfiguration {});
+    }
+    exchange::validate_config(&config.points_exchange)?;
+    Ok(())
+}
+
+/// 当前发放方式下支撑代币奖励的金库资产；铸造模式无需金库支撑
+fn reward_asset(
+    storage: &dyn Storage,
+    payout_method: &crate::msg::PayoutMethod,
+) -> Result<Option<crate::msg::AssetInfo>, ContractError> {
+    match payout_method {
+        crate::msg::PayoutMethod::Native { denom } => {
+            Ok(Some(crate::msg::AssetInfo::Native { denom: denom.clone() }))
+        }
+        crate::msg::PayoutMethod::Cw20Transfer => Ok(Some(crate::msg::AssetInfo::Cw20 {
+            contract_addr: ft_contract_addr(storage)?,
+        })),
+        crate::msg::PayoutMethod::Cw20Mint => Ok(None),
+    }
+}
+
+fn ft_contract_addr(storage: &dyn Storage) -> Result<Addr, ContractError> {
+    let ft = CONTRACTS
+        .may_load(storage, crate::msg::ContractType::Ft)?
+        .ok_or(ContractError::ContractNotFound {})?;
+    Ok(ft.contract_addr)
+}
+
+/// 积分来源须为处于 Active 状态、且被授予 `query_points` 权限的 Registry 合约
+fn registry_contract_addr(storage: &dyn Storage) -> Result<Addr, ContractError> {
+    let registry = CONTRACTS
+        .may_load(storage, crate::msg::ContractType::Registry)?
+        .ok_or(ContractError::ContractNotFound {})?;
+    if registry.status != crate::msg::ContractStatus::Active {
+        return Err(ContractError::ContractNotActive {});
+    }
+    if !registry.capabilities.iter().any(|c| c == crate::msg::CAPABILITY_QUERY_POINTS) {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(registry.contract_addr)
+}
+
+/// 为已领取的奖励生成发放消息，同一用户的同种资产合并为一条
+fn payout_msgs(storage: &mut dyn Storage, rewards: &[&crate::msg::UserReward]) -> Result<Vec<SubMsg>, ContractError> {
+    let mut payouts: std::collections::BTreeMap<(String, String), (Option<crate::msg::AssetInfo>, Uint128)> =
+        std::collections::BTreeMap::new();
+    let mut nft_msgs = vec![];
+    for reward in rewards {
+        if let (crate::msg::RewardStatus::Claimed, Some(nft)) = (&reward.status, &reward.nft) {
+            nft_msgs.push(SubMsg::new(nft::payout_msg(nft, &reward.user)?));
+            continue;
+        }
+        // 分期解锁的奖励由 ClaimVested 按解锁部分发放
+        if reward.status != crate::msg::RewardStatus::Claimed
+            || reward.reward_type != crate::msg::RewardType::Token
+            || reward.amount.is_zero()
+            || reward.vesting.is_some()
+        {
+            continue;
+        }
+        let asset_key = reward.asset.as_ref().map_or_else(|| "mint".to_string(), |asset| asset.key());
+        let entry = payouts.entry((reward.user.clone(), asset_key)).or_insert((reward.asset.clone(), Uint128::zero()));
+        entry.1 += reward.amount;
+    }
+    if payouts.is_empty() {
+        return Ok(nft_msgs);
+    }
+
+    let mint_cap = CONFIG.load(storage)?.mint_cap;
+    let token_msgs = payouts
+        .into_iter()
+        .map(|((user, _), (asset, amount))| token_payout_msg(storage, mint_cap, &Addr::unchecked(user), amount, asset))
+        .collect::<Result<Vec<_>, ContractError>>()?;
+    Ok(token_msgs.into_iter().chain(nft_msgs).collect())
+}
+
+/// 单笔代币发放：金库资产直接转出，铸币计入铸造上限并在失败时经回调报错
+fn token_payout_msg(
+    storage: &mut dyn Storage,
+    mint_cap: Uint128,
+    recipient: &Addr,
+    amount: Uint128,
+    asset: Option<crate::msg::AssetInfo>,
+) -> Result<SubMsg, ContractError> {
+    if asset.is_some() {
+        return Ok(SubMsg::new(build_payout_msg(storage, recipient, amount, asset)?));
+    }
+    treasury::record_mint(storage, mint_cap, amount)?;
+    let payload = crate::state::MintPayload { recipient: recipient.to_string(), amount };
+    Ok(SubMsg::reply_on_error(build_payout_msg(storage, recipient, amount, None)?, REPLY_REWARD_MINT)
+        .with_payload(to_json_binary(&payload)?))
+}
+
+/// 构造代币发放消息：有金库资产时直接转出，否则通过 Ft 合约铸造
+fn build_payout_msg(
+    storage: &dyn Storage,
+    recipient: &Addr,
+    amount: Uint128,
+    asset: Option<crate::msg::AssetInfo>,
+) -> Result<CosmosMsg, ContractError> {
+    let (contract_addr, cw20_msg) = match asset {
+        Some(crate::msg::AssetInfo::Native { denom }) => {
+            return Ok(BankMsg::Send {
+                to_address: recipient.to_string(),
+                amount: vec![Coin { denom, amount }],
+            }
+            .into());
+        }
+        Some(crate::msg::AssetInfo::Cw20 { contract_addr }) => {
+            (contract_addr, Cw20ExecuteMsg::Transfer { recipient: recipient.to_string(), amount })
+        }
+        None => (
+            ft_contract_addr(storage)?,
+            Cw20ExecuteMsg::Mint { recipient: recipient.to_string(), amount },
+        ),
+    };
+
+    Ok(WasmMsg::Execute {
+        contract_addr: contract_addr.to_string(),
+        msg: to_json_binary(&cw20_msg)?,
+        funds: vec![],
+    }
+    .into())
+}
+
+// ===== 查询函数 =====
+
+fn query_config(deps: Deps) -> StdResult<crate::msg::IncentiveConfig> {
+    CONFIG.load(deps.storage)
+}
+
+fn query_reward(deps: Deps, reward_id: String) -> StdResult<crate::msg::RewardResponse> {
+    let (_, reward) = may_load_reward(deps.storage, &reward_id)?
+        .ok_or_else(|| cosmwasm_std::StdError::not_found(format!("reward {}", reward_id)))?;
+    Ok(crate::msg::RewardResponse { owner: reward.user.clone(), reward })
+}
+
+fn query_user_rewards(deps: Deps, user: String) -> StdResult<Vec<crate::msg::UserReward>> {
+    REWARDS
+        .idx
+        .user
+        .prefix(user)
+        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        .map(|item| item.map(|(_, reward)| reward))
+        .collect()
+}
+
+#[allow(clippy::too_many_arguments)]
+fn query_reward_history(
+    deps: Deps,
+    user: String,
+    status: Option<crate::msg::RewardStatus>,
+    activity_type: Option<String>,
+    from_time: Option<cosmwasm_std::Timestamp>,
+    to_time: Option<cosmwasm_std::Timestamp>,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<crate::msg::RewardHistoryResponse> {
+    let limit = limit.unwrap_or(DEFAULT_HISTORY_LIMIT).min(MAX_HISTORY_LIMIT) as usize;
+    let start = start_after
+        .map(|id| reward_key(&id).ok_or_else(|| cosmwasm_std::StdError::parse_err("reward_id", id)))
+        .transpose()?
+        .map(Bound::exclusive);
+
+    // 优先使用最窄的索引，从 start_after 之后开始读取，其余条件逐条过滤；
+    // 合计取自按状态增量维护的统计，不随页数扫描
+    let order = cosmwasm_std::Order::Ascending;
+    let rewards: Box<dyn Iterator<Item = StdResult<(u64, crate::msg::UserReward)>>> = match (&status, &activity_type) {
+        (Some(status), _) => Box::new(
+            REWARDS.idx.status.prefix((user.clone(), status_key(status))).range(deps.storage, start, None, order),
+        ),
+        (None, Some(activity)) => Box::new(
+            REWARDS.idx.activity.prefix((user.clone(), activity.clone())).range(deps.storage, start, None, order),
+        ),
+        (None, None) => Box::new(REWARDS.idx.user.prefix(user.clone()).range(deps.storage, start, None, order)),
+    };
+
+    let mut page = vec![];
+    for item in rewards {
+        let (_, reward) = item?;
+        // 奖励ID随创建时间递增，超出时间窗口后不会再有匹配
+        if to_time.is_some_and(|to| reward.created_at > to) {
+            break;
+        }
+        let matches = activity_type
+            .as_ref()
+            .is_none_or(|activity| rules::activity_type_name(&reward.activity_type) == *activity)
+            && from_time.is_none_or(|from| reward.created_at >= from);
+        if matches {
+            page.push(reward);
+            if page.len() >= limit {
+                break;
+            }
+        }
+    }
+
+    let totals = crate::state::user_reward_totals(deps.storage, &user, status.as_ref())?;
+    Ok(crate::msg::RewardHistoryResponse {
+        rewards: page,
+        total_rewards: totals.amount,
+        total_count: totals.count,
+    })
+}
+
+fn query_rules(deps: Deps) -> StdResult<Vec<crate::msg::RuleDetails>> {
+    let rules: Result<Vec<_>, _> = RULES.range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        .collect();
+    Ok(rules?.into_iter().map(|(_, rule)| rule).collect())
+}
+
+fn query_contracts(
+    deps: Deps,
+    kind: Option<crate::msg::ContractKind>,
+    start_after: Option<crate::msg::ContractType>,
+    limit: Option<u32>,
+) -> StdResult<Vec<crate::msg::ContractInfo>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    match kind {
+        None => {
+            let start = start_after.map(Bound::exclusive);
+            CONTRACTS
+                .range(deps.storage, start, None, cosmwasm_std::Order::Ascending)
+                .take(limit)
+                .map(|item| item.map(|(_, info)| info))
+                .collect()
+        }
+        Some(crate::msg::ContractKind::Custom) => {
+            // 在 "custom" 前缀下按名称分页
+            let start = match start_after {
+                Some(crate::msg::ContractType::Custom(name)) => Some(Bound::exclusive(name)),
+                _ => None,
+            };
+            CONTRACTS
+                .prefix(crate::msg::ContractType::custom_prefix())
+                .range(deps.storage, start, None, cosmwasm_std::Order::Ascending)
+                .take(limit)
+                .map(|item| item.map(|(_, info)| info))
+                .collect()
+        }
+        // 内置类型最多只有一个注册合约
+        Some(crate::msg::ContractKind::Ft) => query_builtin_contract(deps, crate::msg::ContractType::Ft, start_after),
+        Some(crate::msg::ContractKind::BlindBox) => {
+            query_builtin_contract(deps, crate::msg::ContractType::BlindBox, start_after)
+        }
+        Some(crate::msg::ContractKind::Nft) => query_builtin_contract(deps, crate::msg::ContractType::Nft, start_after),
+        Some(crate::msg::ContractKind::Registry) => {
+            query_builtin_contract(deps, crate::msg::ContractType::Registry, start_after)
+        }
+    }
+}
+
+fn query_builtin_contract(
+    deps: Deps,
+    contract_type: crate::msg::ContractType,
+    start_after: Option<crate::msg::ContractType>,
+) -> StdResult<Vec<crate::msg::ContractInfo>> {
+    if start_after.as_ref() == Some(&contract_type) {
+        return Ok(vec![]);
+    }
+    Ok(CONTRACTS.may_load(deps.storage, contract_type)?.into_iter().collect())
+}
+
+fn query_contract(deps: Deps, contract_type: crate::msg::ContractType) -> StdResult<crate::msg::ContractInfo> {
+    CONTRACTS.load(deps.storage, contract_type)
+}
+
+fn query_contract_by_address(deps: Deps, addr: String) -> StdResult<crate::msg::ContractInfo> {
+    let contract_type = CONTRACT_ADDRS.load(deps.storage, addr)?;
+    CONTRACTS.load(deps.storage, contract_type)
+}
+
+fn query_exchange_quota(deps: Deps, env: Env, user: String) -> StdResult<crate::msg::ExchangeQuotaResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    exchange::quota(deps.storage, &config.points_exchange, &user, env.block.time)
+}
+
+fn query_vesting_status(deps: Deps, env: Env, user: String) -> StdResult<crate::msg::VestingStatusResponse> {
+    let mut status = crate::msg::VestingStatusResponse {
+        user: user.clone(),
+        locked: Uint128::zero(),
+        unlocked: Uint128::zero(),
+        claimed: Uint128::zero(),
+        rewards: vec![],
+    };
+
+    // 全部领取后奖励不再是 Pending，只列出仍在解锁中的奖励
+    let pending = REWARDS
+        .idx
+        .status
+        .prefix((user, status_key(&crate::msg::RewardStatus::Pending)))
+        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending);
+    for item in pending {
+        let (_, reward) = item?;
+        let Some(tracked) = reward.vesting.as_ref() else {
+            continue;
+        };
+        let vested = vesting::vested_amount(&reward, env.block.time);
+        let info = crate::msg::VestingInfo {
+            reward_id: reward.reward_id.clone(),
+            total: reward.amount,
+            locked: reward.amount - vested,
+            unlocked: vested - tracked.claimed,
+            claimed: tracked.claimed,
+            schedule: tracked.schedule.clone(),
+            start_time: reward.created_at,
+        };
+        status.locked += info.locked;
+        status.unlocked += info.unlocked;
+        status.claimed += info.claimed;
+        status.rewards.push(info);
+    }
+    Ok(status)
+}
+
+fn query_mint_status(deps: Deps) -> StdResult<crate::msg::MintStatusResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    treasury::mint_status(deps.storage, config.mint_cap)
+}
+
+fn query_nft_escrow(
+    deps: Deps,
+    collection: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<crate::msg::NftEscrowResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let token_ids = nft::escrowed(deps.storage, &collection, start_after, limit)?;
+    Ok(crate::msg::NftEscrowResponse { collection, token_ids })
+}
+
+fn query_admin(deps: Deps) -> StdResult<crate::msg::AdminResponse> {
+    Ok(crate::msg::AdminResponse {
+        admin: ADMIN.may_load(deps.storage)?,
+        pending_admin: PENDING_ADMIN.may_load(deps.storage)?,
+    })
+}
+
+fn query_role_members(
+    deps: Deps,
+    role: crate::msg::Role,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<crate::msg::RoleMembersResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let members = ROLES
+        .prefix(role.key().to_string())
+        .keys(deps.storage, start_after.map(Bound::exclusive), None, cosmwasm_std::Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(Addr::unchecked))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(crate::msg::RoleMembersResponse { role, members })
+}
+
+fn query_pause_status(deps: Deps) -> StdResult<crate::msg::PauseStatusResponse> {
+    let paused = PAUSED
+        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        .map(|item| item.map(|(_, record)| record))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(crate::msg::PauseStatusResponse { paused })
+}
+
+fn query_event_processed(deps: Deps, event_id: String) -> StdResult<crate::msg::EventProcessedResponse> {
+    let event = PROCESSED_EVENTS.may_load(deps.storage, event_id.clone())?;
+    Ok(crate::msg::EventProcessedResponse {
+        event_id,
+        processed: event.is_some(),
+        processed_at: event.as_ref().map(|event| event.processed_at),
+        reward_ids: event.map(|event| event.reward_ids).unwrap_or_default(),
+    })
+}
+
+fn query_auto_claim(deps: Deps, user: String) -> StdResult<crate::msg::AutoClaimResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let opted_out = AUTO_CLAIM_OPT_OUTS.has(deps.storage, user);
+    Ok(crate::msg::AutoClaimResponse {
+        enabled: config.auto_claim_enabled && !opted_out,
+        opted_out,
+        manual_claim_reward_types: config.manual_claim_reward_types,
+    })
+}
+
+fn query_user_level(deps: Deps, user: String) -> StdResult<crate::msg::UserLevelInfo> {
     USER_LEVELS.load(deps.storage, user.clone())
 }
 
-fn query_system_stats(_deps: Deps) -> StdResult<crate::state::SystemStats> {
-    // 简化实现：返回基础统计信息
-    Ok(crate::state::SystemStats {
-        total_users: 0, // 实际实现中应该统计用户数量
-        total_rewards_distributed: Uint128::zero(), // 实际实现中应该统计总奖励
-        total_rules: 0, // 实际实现中应该统计规则数量
-        total_contracts: 0, // 实际实现中应该统计合约数量
-        last_updated: cosmwasm_std::Timestamp::from_seconds(0), // 实际实现中应该使用当前时间
+fn query_system_stats(deps: Deps) -> StdResult<crate::state::SystemStats> {
+    Ok(STATS
+        .may_load(deps.storage)?
+        .unwrap_or_else(|| SystemStats::new(cosmwasm_std::Timestamp::from_seconds(0))))
+}
+
+fn query_activity_stats(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<crate::msg::ActivityStatsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let activities = crate::state::ACTIVITY_STATS
+        .range(deps.storage, start_after.map(Bound::exclusive), None, cosmwasm_std::Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(activity, totals)| crate::msg::ActivityStats { activity, totals }))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(crate::msg::ActivityStatsResponse { activities })
+}
+
+fn query_treasury_balance(deps: Deps, env: Env) -> StdResult<crate::msg::TreasuryBalanceResponse> {
+    let balances = TREASURY
+        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        .map(|item| {
+            let (_, balance) = item?;
+            // 单个资产查询失败不影响其他资产的结果
+            let held = treasury::held_balance(&deps.querier, &balance.asset, &env.contract.address).ok();
+            Ok(crate::msg::TreasuryAssetBalance {
+                asset: balance.asset,
+                available: balance.available,
+                committed: balance.committed,
+                held,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(crate::msg::TreasuryBalanceResponse { balances })
+}
+
+fn query_referral_tree(deps: Deps, user: String, depth: Option<u32>) -> StdResult<crate::msg::ReferralTreeResponse> {
+    let depth = depth.unwrap_or(referral::MAX_REFERRAL_DEPTH).min(referral::MAX_REFERRAL_DEPTH);
+    Ok(crate::msg::ReferralTreeResponse {
+        upline: referral::upline(deps.storage, &user, depth)?,
+        downline: referral::downline(deps.storage, &user, depth)?,
+        user,
     })
 }
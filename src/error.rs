@@ -1,4 +1,5 @@
 use cosmwasm_std::StdError;
+use cw_utils::PaymentError;
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq)]
@@ -6,9 +7,24 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("{0}")]
+    Payment(#[from] PaymentError),
+
     #[error("Unauthorized")]
     Unauthorized {},
 
+    #[error("Operation paused: {scope}")]
+    Paused { scope: String },
+
+    #[error("Cannot migrate from contract {contract}")]
+    WrongContract { contract: String },
+
+    #[error("Cannot migrate from {from} to older version {to}")]
+    CannotDowngrade { from: String, to: String },
+
+    #[error("Invalid version: {version}")]
+    InvalidVersion { version: String },
+
     #[error("Reward not found")]
     RewardNotFound {},
 
@@ -30,21 +46,63 @@ pub enum ContractError {
     #[error("Reward expired")]
     RewardExpired {},
 
+    #[error("Too many pending rewards for user")]
+    RewardLimitExceeded {},
+
+    #[error("Batch too large (max {max})")]
+    BatchTooLarge { max: u32 },
+
+    #[error("Reward is vesting, use ClaimVested")]
+    RewardVesting {},
+
+    #[error("No vested amount to claim")]
+    NothingVested {},
+
+    #[error("NFT not available in escrow")]
+    NftNotAvailable {},
+
+    #[error("Mint cap exceeded")]
+    MintCapExceeded {},
+
+    #[error("Mint failed: {reason}")]
+    MintFailed { reason: String },
+
+    #[error("Points exchange limit exceeded: {limit}")]
+    ExchangeLimitExceeded { limit: String },
+
+    #[error("Batch is empty")]
+    EmptyBatch {},
+
+    #[error("Duplicate user in batch: {user}")]
+    DuplicateUser { user: String },
+
     #[error("Rule already exists")]
     RuleAlreadyExists {},
 
     #[error("Contract already registered")]
     ContractAlreadyRegistered {},
 
+    #[error("Contract is not active")]
+    ContractNotActive {},
+
     #[error("Invalid configuration")]
     InvalidConfiguration {},
 
     #[error("Insufficient balance")]
     InsufficientBalance {},
 
+    #[error("Insufficient points")]
+    InsufficientPoints {},
+
     #[error("Operation not allowed")]
     OperationNotAllowed {},
 
+    #[error("Referrer already bound")]
+    ReferrerAlreadyBound {},
+
+    #[error("Invalid referrer")]
+    InvalidReferrer {},
+
     #[error("System error: {msg}")]
     SystemError { msg: String },
 }
@@ -0,0 +1,135 @@
+use cosmwasm_std::{Order, StdResult, Storage, Timestamp, Uint128};
+use cw_storage_plus::Bound;
+
+use crate::error::ContractError;
+use crate::msg::{ExchangeQuotaResponse, PointsExchangeConfig};
+use crate::state::{EXCHANGE_GLOBAL_VOLUME, EXCHANGE_USER_VOLUME};
+
+// ===== 积分兑换额度 =====
+//
+// 兑换量按小时分桶记录，24 小时额度取当前小时及之前 24 个小时的合计，
+// 即以小时为粒度的滚动窗口：过去 24 小时内的兑换全部计入，最多多计不足一小时。
+// 额度与上限均以积分计，0 表示不限制。
+
+const SECONDS_PER_HOUR: u64 = 3600;
+const WINDOW_HOURS: u64 = 24;
+
+fn hour_of(time: Timestamp) -> u64 {
+    time.seconds() / SECONDS_PER_HOUR
+}
+
+/// 窗口内最早的小时桶，包含 24 小时前所在的小时
+fn window_start(now: Timestamp) -> u64 {
+    hour_of(now).saturating_sub(WINDOW_HOURS)
+}
+
+fn user_used(storage: &dyn Storage, user: &str, now: Timestamp) -> StdResult<Uint128> {
+    EXCHANGE_USER_VOLUME
+        .prefix(user.to_string())
+        .range(storage, Some(Bound::inclusive(window_start(now))), None, Order::Ascending)
+        .try_fold(Uint128::zero(), |total, item| Ok(total + item?.1))
+}
+
+fn global_used(storage: &dyn Storage, now: Timestamp) -> StdResult<Uint128> {
+    EXCHANGE_GLOBAL_VOLUME
+        .range(storage, Some(Bound::inclusive(window_start(now))), None, Order::Ascending)
+        .try_fold(Uint128::zero(), |total, item| Ok(total + item?.1))
+}
+
+fn remaining(cap: Uint128, used: Uint128) -> Option<Uint128> {
+    (!cap.is_zero()).then(|| cap.saturating_sub(used))
+}
+
+/// 兑换比例须为正，单次上限不得低于下限
+pub fn validate_config(config: &PointsExchangeConfig) -> Result<(), ContractError> {
+    if config.rate.is_zero() || (!config.max_points.is_zero() && config.max_points < config.min_points) {
+        return Err(ContractError::InvalidConfiguration {});
+    }
+    Ok(())
+}
+
+/// 积分按兑换比例折算的代币数量（向下取整）
+pub fn tokens_for(config: &PointsExchangeConfig, points: Uint128) -> Uint128 {
+    points.mul_floor(config.rate)
+}
+
+pub fn quota(
+    storage: &dyn Storage,
+    config: &PointsExchangeConfig,
+    user: &str,
+    now: Timestamp,
+) -> StdResult<ExchangeQuotaResponse> {
+    let user_used = user_used(storage, user, now)?;
+    let global_used = global_used(storage, now)?;
+    Ok(ExchangeQuotaResponse {
+        rate: config.rate,
+        min_points: config.min_points,
+        max_points: config.max_points,
+        user_used,
+        user_remaining: remaining(config.user_daily_cap, user_used),
+        global_used,
+        global_remaining: remaining(config.global_daily_cap, global_used),
+    })
+}
+
+/// 校验单次上下限与 24 小时额度，通过后记入当前小时桶
+pub fn consume(
+    storage: &mut dyn Storage,
+    config: &PointsExchangeConfig,
+    user: &str,
+    points: Uint128,
+    now: Timestamp,
+) -> Result<(), ContractError> {
+    if points < config.min_points || points.is_zero() {
+        return Err(ContractError::InvalidAmount {});
+    }
+    if !config.max_points.is_zero() && points > config.max_points {
+        return Err(ContractError::ExchangeLimitExceeded { limit: "max_points".to_string() });
+    }
+
+    let quota = quota(storage, config, user, now)?;
+    if quota.user_remaining.is_some_and(|remaining| points > remaining) {
+        return Err(ContractError::ExchangeLimitExceeded { limit: "user_daily_cap".to_string() });
+    }
+    if quota.global_remaining.is_some_and(|remaining| points > remaining) {
+        return Err(ContractError::ExchangeLimitExceeded { limit: "global_daily_cap".to_string() });
+    }
+
+    prune(storage, user, now)?;
+    let hour = hour_of(now);
+    EXCHANGE_USER_VOLUME.update(storage, (user.to_string(), hour), |used| -> StdResult<_> {
+        Ok(used.unwrap_or_default() + points)
+    })?;
+    EXCHANGE_GLOBAL_VOLUME.update(storage, hour, |used| -> StdResult<_> { Ok(used.unwrap_or_default() + points) })?;
+    Ok(())
+}
+
+/// 兑换失败时退回 `at` 所在小时桶中记入的额度
+pub fn release(storage: &mut dyn Storage, user: &str, points: Uint128, at: Timestamp) -> StdResult<()> {
+    let hour = hour_of(at);
+    let key = (user.to_string(), hour);
+    if let Some(used) = EXCHANGE_USER_VOLUME.may_load(storage, key.clone())? {
+        EXCHANGE_USER_VOLUME.save(storage, key, &used.saturating_sub(points))?;
+    }
+    if let Some(used) = EXCHANGE_GLOBAL_VOLUME.may_load(storage, hour)? {
+        EXCHANGE_GLOBAL_VOLUME.save(storage, hour, &used.saturating_sub(points))?;
+    }
+    Ok(())
+}
+
+/// 删除窗口外的小时桶
+fn prune(storage: &mut dyn Storage, user: &str, now: Timestamp) -> StdResult<()> {
+    let end = Some(Bound::exclusive(window_start(now)));
+    let stale_user = EXCHANGE_USER_VOLUME
+        .prefix(user.to_string())
+        .keys(storage, None, end.clone(), Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for hour in stale_user {
+        EXCHANGE_USER_VOLUME.remove(storage, (user.to_string(), hour));
+    }
+    let stale_global = EXCHANGE_GLOBAL_VOLUME.keys(storage, None, end, Order::Ascending).collect::<StdResult<Vec<_>>>()?;
+    for hour in stale_global {
+        EXCHANGE_GLOBAL_VOLUME.remove(storage, hour);
+    }
+    Ok(())
+}
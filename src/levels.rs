@@ -0,0 +1,46 @@
+use cosmwasm_std::Decimal;
+
+use crate::error::ContractError;
+use crate::msg::{LevelMultiplier, LevelThreshold, UserLevel};
+
+// ===== 等级计算 =====
+
+/// 按阈值计算积分对应的等级：取 min_points 不超过积分的最高等级，无匹配时为 Bronze
+pub fn level_for_points(thresholds: &[LevelThreshold], points: u32) -> UserLevel {
+    thresholds
+        .iter()
+        .rev()
+        .find(|threshold| points >= threshold.min_points)
+        .map(|threshold| threshold.level.clone())
+        .unwrap_or(UserLevel::Bronze)
+}
+
+/// 阈值须按等级严格递增，且所需积分严格递增
+pub fn validate_thresholds(thresholds: &[LevelThreshold]) -> Result<(), ContractError> {
+    let ordered = thresholds.windows(2).all(|pair| {
+        pair[0].level.rank() < pair[1].level.rank() && pair[0].min_points < pair[1].min_points
+    });
+    if !ordered {
+        return Err(ContractError::InvalidConfiguration {});
+    }
+    Ok(())
+}
+
+/// 等级对应的奖励倍数，未配置的等级为 1.0
+pub fn multiplier_for(multipliers: &[LevelMultiplier], level: &UserLevel) -> Decimal {
+    multipliers
+        .iter()
+        .find(|entry| entry.level == *level)
+        .map(|entry| entry.multiplier)
+        .unwrap_or_else(Decimal::one)
+}
+
+/// 每个等级最多配置一次
+pub fn validate_multipliers(multipliers: &[LevelMultiplier]) -> Result<(), ContractError> {
+    for (i, entry) in multipliers.iter().enumerate() {
+        if multipliers[..i].iter().any(|other| other.level == entry.level) {
+            return Err(ContractError::InvalidConfiguration {});
+        }
+    }
+    Ok(())
+}
@@ -14,17 +14,33 @@
 //! ## 使用示例
 //! 
 //! ```rust
-//! use luckee_incentive::msg::{InstantiateMsg, ExecuteMsg, QueryMsg, IncentiveConfig, ActivityType};
+//! use luckee_incentive::msg::{InstantiateMsg, ExecuteMsg, QueryMsg, IncentiveConfig, ActivityType, PayoutMethod, PointsExchangeConfig};
 //! use luckee_incentive::contract::{instantiate, execute, query};
-//! use cosmwasm_std::Uint128;
+//! use cosmwasm_std::{Decimal, Uint128};
 //! 
 //! // 实例化合约
 //! let msg = InstantiateMsg {
 //!     admin: Some("admin".to_string()),
 //!     config: IncentiveConfig {
 //!         auto_claim_enabled: true,
+//!         manual_claim_reward_types: vec![],
 //!         max_rewards_per_user: 1000,
 //!         reward_expiration_days: 30,
+//!         payout_method: PayoutMethod::Native { denom: "uluckee".to_string() },
+//!         mint_cap: Uint128::zero(),
+//!         referral_rates: vec![Decimal::percent(50), Decimal::percent(20), Decimal::percent(10)],
+//!         level_thresholds: vec![],
+//!         level_demotion_enabled: false,
+//!         level_up_reward: Uint128::zero(),
+//!         level_multipliers: vec![],
+//!         max_batch_size: 100,
+//!         points_exchange: PointsExchangeConfig {
+//!             rate: Decimal::one(),
+//!             min_points: Uint128::from(1000u128),
+//!             max_points: Uint128::zero(),
+//!             user_daily_cap: Uint128::zero(),
+//!             global_daily_cap: Uint128::zero(),
+//!         },
 //!     },
 //! };
 //! 
@@ -36,6 +52,7 @@
 //!         box_id: "box1".to_string(),
 //!         nft_kind: "rare".to_string(),
 //!     },
+//!     event_id: None,
 //! };
 //! 
 //! // 查询状态
@@ -46,11 +63,20 @@ pub mod msg;
 pub mod state;
 pub mod contract;
 pub mod error;
+pub mod access;
+pub mod exchange;
+pub mod levels;
+pub mod migrations;
+pub mod nft;
+pub mod referral;
+pub mod rules;
+pub mod treasury;
+pub mod vesting;
 
 // 测试模块
 #[cfg(test)]
 mod tests;
 
 pub use crate::error::ContractError;
-pub use crate::contract::{instantiate, execute, query};
+pub use crate::contract::{instantiate, execute, query, migrate, reply};
 pub use crate::msg::*;
@@ -0,0 +1,179 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{attr, Attribute, Decimal, Env, Order, StdError, StdResult, Storage, Timestamp, Uint128};
+use cw_storage_plus::Map;
+use semver::Version;
+
+use crate::msg::{ActivityType, AssetInfo, ContractType, PayoutMethod, RewardStatus, RewardType, UserReward};
+use crate::state::{
+    record_reward_created, reward_key, track_user, update_stats, CONFIG, CONTRACTS, CONTRACT_ADDRS, EXPIRY_QUEUE,
+    REWARDS, RULES, USER_LEVELS,
+};
+use crate::treasury;
+
+// ===== 迁移步骤注册表 =====
+
+/// 一次存储布局变更，从低于 `version` 的版本升级时执行
+pub struct MigrationStep {
+    pub version: &'static str,
+    pub name: &'static str,
+    pub run: fn(&mut dyn Storage, &Env) -> StdResult<Vec<Attribute>>,
+}
+
+/// 按版本升序排列，新增布局变更时在末尾追加
+pub const MIGRATION_STEPS: &[MigrationStep] = &[
+    MigrationStep {
+        version: "0.2.0",
+        name: "index_user_rewards",
+        run: migrate_user_rewards,
+    },
+    MigrationStep {
+        version: "0.2.0",
+        name: "index_contract_addrs",
+        run: index_contract_addrs,
+    },
+    MigrationStep {
+        version: "0.2.0",
+        name: "backfill_stats",
+        run: backfill_stats,
+    },
+];
+
+/// 依次执行 (from, to] 区间内的迁移步骤，返回各步骤产生的属性
+pub fn run_steps(storage: &mut dyn Storage, env: &Env, from: &Version, to: &Version) -> StdResult<Vec<Attribute>> {
+    let mut attributes = vec![];
+    for step in MIGRATION_STEPS {
+        let version = Version::parse(step.version).map_err(|err| StdError::generic_err(err.to_string()))?;
+        if *from < version && version <= *to {
+            attributes.push(attr("step", step.name));
+            attributes.extend((step.run)(storage, env)?);
+        }
+    }
+    Ok(attributes)
+}
+
+// ===== 旧版存储布局 =====
+
+/// 旧版按用户保存整组奖励的存储
+const LEGACY_USER_REWARDS: Map<String, Vec<LegacyUserReward>> = Map::new("user_rewards");
+
+/// 旧版奖励结构，后续新增的字段均可缺省
+#[cw_serde]
+struct LegacyUserReward {
+    reward_id: String,
+    user: String,
+    amount: Uint128,
+    reward_type: RewardType,
+    activity_type: ActivityType,
+    created_at: Timestamp,
+    claimed_at: Option<Timestamp>,
+    expires_at: Option<Timestamp>,
+    status: RewardStatus,
+    #[serde(default)]
+    asset: Option<AssetInfo>,
+    #[serde(default)]
+    base_amount: Option<Uint128>,
+    #[serde(default)]
+    multiplier: Option<Decimal>,
+}
+
+// ===== 迁移步骤 =====
+
+/// 0.2.0：将 `user_rewards` 中的奖励逐条迁入索引表并重建统计。
+/// 旧版代币奖励没有记录资产，按新配置的发放方式补上；待领取部分记入金库的锁定余额。
+fn migrate_user_rewards(storage: &mut dyn Storage, env: &Env) -> StdResult<Vec<Attribute>> {
+    let now = env.block.time;
+    let payout_asset = payout_asset(storage)?;
+    let legacy = LEGACY_USER_REWARDS
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut migrated = 0;
+    for (user, rewards) in legacy {
+        for legacy in rewards {
+            let key = reward_key(&legacy.reward_id)
+                .ok_or_else(|| StdError::parse_err("reward_id", legacy.reward_id.clone()))?;
+            let backfilled = legacy.asset.is_none() && legacy.reward_type == RewardType::Token;
+            let asset = if backfilled { payout_asset.clone() } else { legacy.asset };
+            let reward = UserReward {
+                base_amount: legacy.base_amount.unwrap_or(legacy.amount),
+                multiplier: legacy.multiplier.unwrap_or_else(Decimal::one),
+                reward_id: legacy.reward_id,
+                user: legacy.user,
+                amount: legacy.amount,
+                reward_type: legacy.reward_type,
+                activity_type: legacy.activity_type,
+                created_at: legacy.created_at,
+                claimed_at: legacy.claimed_at,
+                expires_at: legacy.expires_at,
+                status: legacy.status,
+                asset,
+                nft: None,
+                vesting: None,
+            };
+
+            // 旧版没有金库记账：合约已持有这部分资产，入账后立即锁定给待领取奖励
+            if let (true, RewardStatus::Pending, Some(asset)) = (backfilled, &reward.status, &reward.asset) {
+                treasury::deposit(storage, asset, reward.amount).map_err(|err| StdError::generic_err(err.to_string()))?;
+                treasury::commit(storage, asset, reward.amount).map_err(|err| StdError::generic_err(err.to_string()))?;
+            }
+
+            if let (RewardStatus::Pending, Some(expires_at)) = (&reward.status, reward.expires_at) {
+                EXPIRY_QUEUE.save(storage, (expires_at.seconds(), reward.reward_id.clone()), &reward.user)?;
+            }
+            REWARDS.save(storage, key, &reward)?;
+            record_reward_created(storage, &reward, now)?;
+            migrated += 1;
+        }
+        LEGACY_USER_REWARDS.remove(storage, user);
+    }
+    Ok(vec![attr("migrated_rewards", migrated.to_string())])
+}
+
+/// 新配置的发放方式对应的金库资产，铸造模式为 None
+fn payout_asset(storage: &dyn Storage) -> StdResult<Option<AssetInfo>> {
+    match CONFIG.load(storage)?.payout_method {
+        PayoutMethod::Native { denom } => Ok(Some(AssetInfo::Native { denom })),
+        PayoutMethod::Cw20Transfer => {
+            let ft = CONTRACTS
+                .may_load(storage, ContractType::Ft)?
+                .ok_or_else(|| StdError::generic_err("Cw20Transfer payout requires a registered Ft contract"))?;
+            Ok(Some(AssetInfo::Cw20 { contract_addr: ft.contract_addr }))
+        }
+        PayoutMethod::Cw20Mint => Ok(None),
+    }
+}
+
+/// 0.2.0：为已注册的合约建立地址反查索引
+fn index_contract_addrs(storage: &mut dyn Storage, _env: &Env) -> StdResult<Vec<Attribute>> {
+    let contracts = CONTRACTS
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (contract_type, info) in &contracts {
+        CONTRACT_ADDRS.save(storage, info.contract_addr.to_string(), contract_type)?;
+    }
+    Ok(vec![attr("indexed_contracts", contracts.len().to_string())])
+}
+
+/// 0.2.0：按现有规则、合约与用户等级补齐系统统计。
+/// 奖励相关统计与奖励用户已在 `index_user_rewards` 中计入，这里补上仅有等级记录的用户。
+fn backfill_stats(storage: &mut dyn Storage, env: &Env) -> StdResult<Vec<Attribute>> {
+    let now = env.block.time;
+    let users = USER_LEVELS
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for user in &users {
+        track_user(storage, user, now)?;
+    }
+
+    let total_rules = RULES.keys_raw(storage, None, None, Order::Ascending).count() as u32;
+    let total_contracts = CONTRACTS.keys_raw(storage, None, None, Order::Ascending).count() as u32;
+    let stats = update_stats(storage, now, |stats| {
+        stats.total_rules = total_rules;
+        stats.total_contracts = total_contracts;
+    })?;
+    Ok(vec![
+        attr("total_rules", stats.total_rules.to_string()),
+        attr("total_contracts", stats.total_contracts.to_string()),
+        attr("total_users", stats.total_users.to_string()),
+    ])
+}
@@ -1,6 +1,7 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Addr, Uint128, Decimal, Timestamp};
-use cw_storage_plus::{PrimaryKey, Key};
+use cosmwasm_std::{Addr, Binary, Uint128, Decimal, StdError, StdResult, Timestamp};
+use cw20::Cw20ReceiveMsg;
+use cw_storage_plus::{Key, KeyDeserialize, Prefixer, PrimaryKey};
 
 // ===== 实例化消息 =====
 
@@ -14,7 +15,73 @@ pub struct InstantiateMsg {
 pub struct IncentiveConfig {
     pub max_rewards_per_user: u32,
     pub reward_expiration_days: u64,
+    /// 开启后分发即发放，奖励直接记为 Claimed
     pub auto_claim_enabled: bool,
+    /// 即使开启自动领取也必须手动领取的奖励类型（如 NFT）
+    pub manual_claim_reward_types: Vec<RewardType>,
+    pub payout_method: PayoutMethod,
+    /// 通过 Ft 合约累计铸造的代币上限，0 表示不限制
+    pub mint_cap: Uint128,
+    /// 各层推荐奖励比例，下标 0 为直推，最多三层
+    pub referral_rates: Vec<Decimal>,
+    /// 各等级所需积分，按等级升序排列
+    pub level_thresholds: Vec<LevelThreshold>,
+    /// 积分减少时是否降级
+    pub level_demotion_enabled: bool,
+    /// 每次升级生成的 LevelUp 奖励金额，0 表示不发放；
+    /// 分发暂停、金库不足或待领取奖励已达上限时跳过奖励，升级照常生效
+    pub level_up_reward: Uint128,
+    /// 分发时按用户等级应用的奖励倍数，未配置的等级为 1.0；
+    /// 升级奖励为固定金额、推荐奖励按比例从被推荐人的奖励中折算，两者均不再应用倍数
+    pub level_multipliers: Vec<LevelMultiplier>,
+    /// DistributeRewards 单次最多条目数
+    pub max_batch_size: u32,
+    pub points_exchange: PointsExchangeConfig,
+}
+
+/// 积分兑换参数，上限与额度均以积分计，0 表示不限制
+#[cw_serde]
+pub struct PointsExchangeConfig {
+    /// 每积分兑换的代币数量
+    pub rate: Decimal,
+    pub min_points: Uint128,
+    pub max_points: Uint128,
+    /// 每个用户滚动 24 小时内的兑换上限
+    pub user_daily_cap: Uint128,
+    /// 全体用户滚动 24 小时内的兑换上限
+    pub global_daily_cap: Uint128,
+}
+
+#[cw_serde]
+pub struct LevelMultiplier {
+    pub level: UserLevel,
+    pub multiplier: Decimal,
+}
+
+#[cw_serde]
+pub struct LevelThreshold {
+    pub level: UserLevel,
+    pub min_points: u32,
+}
+
+/// 领取代币奖励时的发放方式
+#[cw_serde]
+pub enum PayoutMethod {
+    /// 通过已注册的 `ContractType::Ft` 合约铸造 CW20 代币（本合约需为 minter）
+    Cw20Mint,
+    /// 从本合约持有的 CW20 余额中转账
+    Cw20Transfer,
+    /// 发送本合约持有的原生代币
+    Native { denom: String },
+}
+
+// ===== 迁移消息 =====
+
+/// 迁移前校验 cw2 记录的合约名称与版本，只允许同名合约升级或同版本重新迁移
+#[cw_serde]
+pub struct MigrateMsg {
+    /// 旧版本配置缺少新字段时，需随迁移提供完整配置
+    pub config: Option<IncentiveConfig>,
 }
 
 // ===== 执行消息 =====
@@ -26,10 +93,52 @@ pub enum ExecuteMsg {
         user: String,
         amount: Uint128,
         activity_type: ActivityType,
+        /// 外部事件ID，相同ID只处理一次
+        event_id: Option<String>,
+    },
+    /// 分发一笔 NFT 奖励，领取时铸造或从托管中转出
+    DistributeNftReward {
+        user: String,
+        source: NftRewardSource,
+        activity_type: ActivityType,
+        /// 外部事件ID，相同ID只处理一次
+        event_id: Option<String>,
+    },
+    /// 批量分发，任一条目失败则整批回滚
+    DistributeRewards {
+        entries: Vec<DistributionEntry>,
     },
     ClaimReward {
         reward_id: String,
     },
+    /// 批量领取指定奖励，逐条返回结果，单条失败不影响其余奖励
+    ClaimRewards {
+        reward_ids: Vec<String>,
+    },
+    /// 领取调用者全部未过期的待领取奖励，解锁中的奖励除外
+    ClaimAll {
+        limit: Option<u32>,
+    },
+    /// 领取解锁中奖励已解锁的部分，全部领取后奖励记为 Claimed
+    ClaimVested {
+        reward_id: String,
+    },
+    /// 管理员作废一笔待领取奖励，锁定的金库余额退回
+    CancelReward {
+        reward_id: String,
+    },
+    /// 将已过期的待领取奖励标记为 Expired，任何人都可调用
+    ExpireRewards {
+        limit: Option<u32>,
+    },
+    /// 上报业务事实，由已启用的规则计算奖励
+    ReportActivity {
+        user: String,
+        activity_type: ActivityType,
+        value: Uint128,
+        /// 外部事件ID，相同ID只处理一次
+        event_id: Option<String>,
+    },
     
     // 积分兑换
     MintForPoints {
@@ -53,10 +162,21 @@ pub enum ExecuteMsg {
     RegisterContract {
         contract_type: ContractType,
         contract_addr: String,
+        #[serde(default)]
+        capabilities: Vec<String>,
+    },
+    UpdateContract {
+        contract_type: ContractType,
+        status: Option<ContractStatus>,
+        capabilities: Option<Vec<String>>,
     },
     
-    // 用户等级
-    UpdateUserLevel {
+    // 用户等级（按增量修改积分，避免并发覆盖）
+    AddPoints {
+        user: String,
+        points: u32,
+    },
+    SubtractPoints {
         user: String,
         points: u32,
     },
@@ -65,6 +185,72 @@ pub enum ExecuteMsg {
     UpdateConfig {
         config: IncentiveConfig,
     },
+
+    // 管理员与角色
+    /// 提名新管理员，需由新管理员调用 AcceptAdmin 完成转移
+    ProposeAdmin {
+        new_admin: String,
+    },
+    AcceptAdmin {},
+    /// 永久放弃管理员权限，已授予的角色仍然有效
+    RenounceAdmin {},
+    GrantRole {
+        role: Role,
+        account: String,
+    },
+    RevokeRole {
+        role: Role,
+        account: String,
+    },
+
+    // 紧急暂停
+    /// 拥有 Pauser 角色的地址或管理员暂停指定范围的操作
+    Pause {
+        scope: PauseScope,
+    },
+    Unpause {
+        scope: PauseScope,
+    },
+
+    /// 调用者开启或关闭自己的自动领取，仅在全局开启时生效
+    SetAutoClaim {
+        enabled: bool,
+    },
+
+    // 推荐关系
+    BindReferrer {
+        referrer: String,
+    },
+
+    // 金库管理
+    FundTreasury {},
+    Receive(Cw20ReceiveMsg),
+    WithdrawTreasury {
+        asset: AssetInfo,
+        amount: Uint128,
+        recipient: Option<String>,
+    },
+
+    // NFT 托管
+    ReceiveNft(Cw721ReceiveMsg),
+    /// 取回未分配给奖励的托管 NFT
+    WithdrawNft {
+        collection: String,
+        token_id: String,
+        recipient: Option<String>,
+    },
+}
+
+/// CW20 `Send` 附带的钩子消息
+#[cw_serde]
+pub enum ReceiveMsg {
+    FundTreasury {},
+}
+
+/// CW721 `SendNft` 附带的钩子消息
+#[cw_serde]
+pub enum ReceiveNftMsg {
+    DepositEscrow {},
 }
 
 // ===== 查询消息 =====
@@ -77,18 +263,96 @@ pub enum QueryMsg {
     
     #[returns(Vec<UserReward>)]
     UserRewards { user: String },
+
+    #[returns(RewardResponse)]
+    Reward { reward_id: String },
+
+    /// 按条件分页查询用户奖励历史，按奖励ID升序返回
+    #[returns(RewardHistoryResponse)]
+    RewardHistory {
+        user: String,
+        status: Option<RewardStatus>,
+        /// 活动名称，与规则条件中的名称一致（如 "blind_box_open"）
+        activity_type: Option<String>,
+        /// 按创建时间过滤（闭区间）。索引不含时间，设置 from_time 时仍从用户的第一条奖励开始扫描，
+        /// 长历史应配合 start_after 翻页
+        from_time: Option<Timestamp>,
+        to_time: Option<Timestamp>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
     
     #[returns(Vec<RuleDetails>)]
     Rules {},
     
     #[returns(Vec<ContractInfo>)]
-    Contracts {},
+    Contracts {
+        kind: Option<ContractKind>,
+        start_after: Option<ContractType>,
+        limit: Option<u32>,
+    },
+
+    #[returns(ContractInfo)]
+    Contract { contract_type: ContractType },
+
+    #[returns(ContractInfo)]
+    ContractByAddress { addr: String },
     
     #[returns(UserLevelInfo)]
     UserLevel { user: String },
+
+    #[returns(AutoClaimResponse)]
+    AutoClaim { user: String },
+
+    #[returns(EventProcessedResponse)]
+    EventProcessed { event_id: String },
+
+    #[returns(PauseStatusResponse)]
+    PauseStatus {},
+
+    #[returns(ExchangeQuotaResponse)]
+    ExchangeQuota { user: String },
+
+    #[returns(MintStatusResponse)]
+    MintStatus {},
+
+    /// 用户仍在解锁中的奖励及锁定、已解锁、已领取数量
+    #[returns(VestingStatusResponse)]
+    VestingStatus { user: String },
+
+    #[returns(AdminResponse)]
+    Admin {},
+
+    #[returns(RoleMembersResponse)]
+    RoleMembers {
+        role: Role,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
     
     #[returns(crate::state::SystemStats)]
     SystemStats {},
+
+    /// 按活动名称升序列出各活动创建的奖励统计
+    #[returns(ActivityStatsResponse)]
+    ActivityStats {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    #[returns(TreasuryBalanceResponse)]
+    TreasuryBalance {},
+
+    /// 按 token_id 升序列出某合约下可用于奖励的托管 NFT
+    #[returns(NftEscrowResponse)]
+    NftEscrow {
+        collection: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    #[returns(ReferralTreeResponse)]
+    ReferralTree { user: String, depth: Option<u32> },
 }
 
 // ===== 数据结构 =====
@@ -97,6 +361,7 @@ pub enum QueryMsg {
 pub enum ActivityType {
     BlindBoxOpen { nft_kind: String, box_id: String },
     NftExchange { nft_id: String, amount: Uint128 },
+    /// 上线获得的推荐奖励，`referrer` 记录触发该奖励的下线用户
     Referral { referrer: String },
     LevelUp { new_level: UserLevel },
     Custom { activity_id: String },
@@ -113,14 +378,41 @@ pub enum UserLevel {
     GrandMaster,
 }
 
+impl UserLevel {
+    /// 等级序号，Bronze 为 0，数值越大等级越高
+    pub fn rank(&self) -> u8 {
+        match self {
+            UserLevel::Bronze => 0,
+            UserLevel::Silver => 1,
+            UserLevel::Gold => 2,
+            UserLevel::Platinum => 3,
+            UserLevel::Diamond => 4,
+            UserLevel::Master => 5,
+            UserLevel::GrandMaster => 6,
+        }
+    }
+}
+
 #[cw_serde]
 pub enum ContractType {
     Ft,
     BlindBox,
     Nft,
+    /// DD Registry，积分兑换时查询并扣除用户积分
+    Registry,
     Custom(String),
 }
 
+/// Contracts 查询的类型过滤，`Custom` 匹配所有自定义合约
+#[cw_serde]
+pub enum ContractKind {
+    Ft,
+    BlindBox,
+    Nft,
+    Registry,
+    Custom,
+}
+
 #[cw_serde]
 pub struct RuleDetails {
     pub rule_id: String,
@@ -172,7 +464,82 @@ pub struct RewardDefinition {
     pub reward_type: RewardType,
     pub amount: Uint128,
     pub multiplier: Decimal,
+    /// 保留字段，须为空；匹配条件请设置在 `RuleDetails.conditions`
     pub conditions: Vec<RewardCondition>,
+    /// `RewardType::Nft` 奖励的来源，其他类型为 None
+    pub nft: Option<NftRewardSource>,
+    /// 代币奖励的解锁计划，None 表示可一次性领取
+    pub vesting: Option<VestingSchedule>,
+}
+
+/// 解锁计划，从奖励创建时开始计时，时长均以秒计
+#[cw_serde]
+pub struct VestingSchedule {
+    /// 锁定期，期间不解锁
+    pub cliff_seconds: u64,
+    /// 自创建起到全部解锁的总时长
+    pub duration_seconds: u64,
+    /// 解锁间隔，0 表示按秒线性解锁
+    pub period_seconds: u64,
+}
+
+#[cw_serde]
+pub struct RewardVesting {
+    pub schedule: VestingSchedule,
+    /// 已通过 ClaimVested 领取的数量
+    pub claimed: Uint128,
+}
+
+/// NFT 奖励的来源
+#[cw_serde]
+pub enum NftRewardSource {
+    /// 由已注册的 `ContractType::Nft` 合约铸造；模板中的 `{id}` 替换为奖励序号，`{user}` 替换为用户地址
+    Mint {
+        token_id_template: String,
+        token_uri: Option<String>,
+        extension: Option<NftMetadata>,
+    },
+    /// 从本合约托管的 NFT 中转出，未指定 token_id 时取该合约下 token_id 最小的一枚
+    Escrow {
+        collection: String,
+        token_id: Option<String>,
+    },
+}
+
+/// 铸造时写入的链上元数据，字段与 cw721-metadata-onchain 一致
+#[cw_serde]
+pub struct NftMetadata {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub image: Option<String>,
+    pub external_url: Option<String>,
+    pub attributes: Option<Vec<NftTrait>>,
+}
+
+#[cw_serde]
+pub struct NftTrait {
+    pub display_type: Option<String>,
+    pub trait_type: String,
+    pub value: String,
+}
+
+/// 分发时已确定的 NFT 奖励
+#[cw_serde]
+pub struct NftReward {
+    pub collection: Addr,
+    pub token_id: String,
+    pub delivery: NftDelivery,
+}
+
+#[cw_serde]
+pub enum NftDelivery {
+    /// 领取时铸造给用户
+    Mint {
+        token_uri: Option<String>,
+        extension: Option<NftMetadata>,
+    },
+    /// 领取时从托管中转出，作废或过期后退回托管
+    Transfer,
 }
 
 #[cw_serde]
@@ -193,13 +560,24 @@ pub struct RewardCondition {
 pub struct UserReward {
     pub reward_id: String,
     pub user: String,
+    /// 实际奖励金额 = base_amount * multiplier
     pub amount: Uint128,
+    /// 应用等级倍数前的基础金额
+    pub base_amount: Uint128,
+    /// 分发时应用的等级倍数
+    pub multiplier: Decimal,
     pub reward_type: RewardType,
     pub activity_type: ActivityType,
     pub created_at: Timestamp,
     pub claimed_at: Option<Timestamp>,
     pub expires_at: Option<Timestamp>,
     pub status: RewardStatus,
+    /// 支撑该奖励的金库资产；`None` 表示领取时铸造或非代币奖励
+    pub asset: Option<AssetInfo>,
+    /// NFT 奖励的合约与 token_id
+    pub nft: Option<NftReward>,
+    /// 解锁计划与已领取数量，需通过 ClaimVested 分期领取
+    pub vesting: Option<RewardVesting>,
 }
 
 #[cw_serde]
@@ -219,6 +597,12 @@ pub struct ContractInfo {
     pub registered_at: Timestamp,
 }
 
+/// 注册合约可被授予的权限
+pub const CAPABILITY_DISTRIBUTE_REWARD: &str = "distribute_reward";
+pub const CAPABILITY_MINT_TOKENS: &str = "mint_tokens";
+pub const CAPABILITY_QUERY_POINTS: &str = "query_points";
+pub const CAPABILITIES: [&str; 3] = [CAPABILITY_DISTRIBUTE_REWARD, CAPABILITY_MINT_TOKENS, CAPABILITY_QUERY_POINTS];
+
 #[cw_serde]
 pub enum ContractStatus {
     Active,
@@ -236,8 +620,268 @@ pub struct UserLevelInfo {
     pub total_rewards: Uint128,
 }
 
+/// 金库中记账的资产
+#[cw_serde]
+pub enum AssetInfo {
+    Native { denom: String },
+    Cw20 { contract_addr: Addr },
+}
+
+impl AssetInfo {
+    /// 金库存储使用的键
+    pub fn key(&self) -> String {
+        match self {
+            AssetInfo::Native { denom } => format!("native:{}", denom),
+            AssetInfo::Cw20 { contract_addr } => format!("cw20:{}", contract_addr),
+        }
+    }
+}
+
+#[cw_serde]
+pub struct TreasuryBalance {
+    pub asset: AssetInfo,
+    /// 可用于新奖励的余额
+    pub available: Uint128,
+    /// 已分配给未领取奖励的余额
+    pub committed: Uint128,
+}
+
 // ===== 响应类型 =====
 
+/// 紧急暂停的范围，`All` 覆盖其余全部范围
+#[cw_serde]
+pub enum PauseScope {
+    /// 另外还暂停 CancelReward / WithdrawTreasury / WithdrawNft
+    All,
+    /// DistributeReward / DistributeRewards / DistributeNftReward / ReportActivity 及升级奖励
+    Distribution,
+    /// ClaimReward / ClaimRewards / ClaimAll / ClaimVested，期间自动领取也不发放
+    Claims,
+    /// MintForPoints
+    PointsExchange,
+    /// CreateRule / UpdateRule / DeleteRule
+    RuleChanges,
+}
+
+impl PauseScope {
+    /// 暂停状态存储使用的键
+    pub fn key(&self) -> &'static str {
+        match self {
+            PauseScope::All => "all",
+            PauseScope::Distribution => "distribution",
+            PauseScope::Claims => "claims",
+            PauseScope::PointsExchange => "points_exchange",
+            PauseScope::RuleChanges => "rule_changes",
+        }
+    }
+}
+
+#[cw_serde]
+pub struct PausedScope {
+    pub scope: PauseScope,
+    pub paused_at: Timestamp,
+}
+
+#[cw_serde]
+pub struct PauseStatusResponse {
+    pub paused: Vec<PausedScope>,
+}
+
+/// 可授予非管理员地址的角色，管理员隐含拥有全部角色
+#[cw_serde]
+pub enum Role {
+    /// CreateRule / UpdateRule / DeleteRule
+    RuleManager,
+    /// 分发、上报活动、积分兑换与作废奖励
+    Distributor,
+    /// Pause / Unpause
+    Pauser,
+    /// WithdrawTreasury
+    Treasurer,
+    /// AddPoints / SubtractPoints
+    LevelOperator,
+}
+
+impl Role {
+    /// 角色存储使用的键
+    pub fn key(&self) -> &'static str {
+        match self {
+            Role::RuleManager => "rule_manager",
+            Role::Distributor => "distributor",
+            Role::Pauser => "pauser",
+            Role::Treasurer => "treasurer",
+            Role::LevelOperator => "level_operator",
+        }
+    }
+}
+
+#[cw_serde]
+pub struct ExchangeQuotaResponse {
+    pub rate: Decimal,
+    pub min_points: Uint128,
+    pub max_points: Uint128,
+    /// 用户最近 24 小时已兑换的积分
+    pub user_used: Uint128,
+    /// None 表示不限制
+    pub user_remaining: Option<Uint128>,
+    pub global_used: Uint128,
+    pub global_remaining: Option<Uint128>,
+}
+
+#[cw_serde]
+pub struct VestingInfo {
+    pub reward_id: String,
+    pub total: Uint128,
+    pub locked: Uint128,
+    /// 已解锁但尚未领取
+    pub unlocked: Uint128,
+    pub claimed: Uint128,
+    pub schedule: VestingSchedule,
+    pub start_time: Timestamp,
+}
+
+#[cw_serde]
+pub struct VestingStatusResponse {
+    pub user: String,
+    pub locked: Uint128,
+    pub unlocked: Uint128,
+    pub claimed: Uint128,
+    pub rewards: Vec<VestingInfo>,
+}
+
+#[cw_serde]
+pub struct MintStatusResponse {
+    pub total_minted: Uint128,
+    pub mint_cap: Uint128,
+    /// None 表示不限制
+    pub remaining: Option<Uint128>,
+}
+
+#[cw_serde]
+pub struct AdminResponse {
+    /// 放弃管理员后为 None
+    pub admin: Option<Addr>,
+    pub pending_admin: Option<Addr>,
+}
+
+#[cw_serde]
+pub struct RoleMembersResponse {
+    pub role: Role,
+    pub members: Vec<Addr>,
+}
+
+#[cw_serde]
+pub struct EventProcessedResponse {
+    pub event_id: String,
+    pub processed: bool,
+    /// 处理该事件时创建的奖励（含推荐奖励）
+    pub reward_ids: Vec<String>,
+    pub processed_at: Option<Timestamp>,
+}
+
+#[cw_serde]
+pub struct AutoClaimResponse {
+    /// 全局开关与用户设置共同决定的结果
+    pub enabled: bool,
+    pub opted_out: bool,
+    /// 始终需要手动领取的奖励类型
+    pub manual_claim_reward_types: Vec<RewardType>,
+}
+
+#[cw_serde]
+pub struct DistributionEntry {
+    pub user: String,
+    pub amount: Uint128,
+    pub activity_type: ActivityType,
+}
+
+/// 批量分发的响应数据
+#[cw_serde]
+pub struct DistributeRewardsResponse {
+    pub count: u32,
+    /// 直接奖励合计（已应用等级倍数，不含推荐奖励）
+    pub total_amount: Uint128,
+    pub first_reward_id: String,
+    pub last_reward_id: String,
+}
+
+/// 批量领取中单条奖励的处理结果
+#[cw_serde]
+pub struct ClaimOutcome {
+    pub reward_id: String,
+    pub claimed: bool,
+    /// 领取失败时的错误信息
+    pub error: Option<String>,
+}
+
+/// 批量领取的响应数据
+#[cw_serde]
+pub struct ClaimRewardsResponse {
+    pub outcomes: Vec<ClaimOutcome>,
+    pub claimed_count: u32,
+}
+
+#[cw_serde]
+pub struct RewardResponse {
+    pub owner: String,
+    pub reward: UserReward,
+}
+
+#[cw_serde]
+pub struct RewardHistoryResponse {
+    pub rewards: Vec<UserReward>,
+    /// 用户在 `status` 过滤下（未指定时为全部状态）的奖励金额合计，不受其他过滤条件与分页影响
+    pub total_rewards: Uint128,
+    /// 同上，奖励数量合计
+    pub total_count: u64,
+}
+
+#[cw_serde]
+pub struct TreasuryBalanceResponse {
+    pub balances: Vec<TreasuryAssetBalance>,
+}
+
+/// 金库账面余额与合约实际持有量
+#[cw_serde]
+pub struct TreasuryAssetBalance {
+    pub asset: AssetInfo,
+    pub available: Uint128,
+    pub committed: Uint128,
+    /// 合约实际持有的数量，正常情况下不少于 available + committed；查询失败时为 None
+    pub held: Option<Uint128>,
+}
+
+#[cw_serde]
+pub struct ActivityStats {
+    pub activity: String,
+    pub totals: crate::state::RewardTotals,
+}
+
+#[cw_serde]
+pub struct ActivityStatsResponse {
+    pub activities: Vec<ActivityStats>,
+}
+
+#[cw_serde]
+pub struct NftEscrowResponse {
+    pub collection: String,
+    pub token_ids: Vec<String>,
+}
+
+#[cw_serde]
+pub struct ReferralNode {
+    pub user: String,
+    /// 与查询用户之间的层级，1 为直接关系
+    pub depth: u32,
+}
+
+#[cw_serde]
+pub struct ReferralTreeResponse {
+    pub user: String,
+    pub upline: Vec<ReferralNode>,
+    pub downline: Vec<ReferralNode>,
+}
+
 #[cw_serde]
 pub struct InstantiateResponse {
     pub contract_addr: Addr,
@@ -257,19 +901,113 @@ pub struct QueryResponse<T> {
     pub timestamp: Timestamp,
 }
 
+// ContractType 的存储键：内置类型为单段键，Custom 为 ("custom", name) 两段键，
+// 前缀为 "custom" 时可按名称遍历全部自定义合约。
+const CUSTOM_CONTRACT_NAMESPACE: &[u8] = b"custom";
+
+// ===== DD Registry 接口 =====
+
+#[cw_serde]
+pub enum RegistryQueryMsg {
+    UserPoints { user: String },
+}
+
+#[cw_serde]
+pub struct UserPointsResponse {
+    pub user: String,
+    pub points: Uint128,
+}
+
+#[cw_serde]
+pub enum RegistryExecuteMsg {
+    WithdrawPoints { user: String, amount: Uint128 },
+}
+
+// ===== CW721 接口 =====
+
+#[cw_serde]
+pub struct Cw721ReceiveMsg {
+    pub sender: String,
+    pub token_id: String,
+    pub msg: Binary,
+}
+
+#[cw_serde]
+pub enum Cw721ExecuteMsg {
+    TransferNft {
+        recipient: String,
+        token_id: String,
+    },
+    Mint {
+        token_id: String,
+        owner: String,
+        token_uri: Option<String>,
+        extension: Option<NftMetadata>,
+    },
+}
+
+impl ContractType {
+    pub fn kind(&self) -> ContractKind {
+        match self {
+            ContractType::Ft => ContractKind::Ft,
+            ContractType::BlindBox => ContractKind::BlindBox,
+            ContractType::Nft => ContractKind::Nft,
+            ContractType::Registry => ContractKind::Registry,
+            ContractType::Custom(_) => ContractKind::Custom,
+        }
+    }
+
+    /// 遍历 Custom 合约时使用的存储前缀
+    pub fn custom_prefix() -> String {
+        String::from_utf8_lossy(CUSTOM_CONTRACT_NAMESPACE).into_owned()
+    }
+}
+
 // 为ContractType实现PrimaryKey trait
 impl<'a> PrimaryKey<'a> for ContractType {
-    type Prefix = ();
+    type Prefix = String;
     type SubPrefix = ();
-    type Suffix = ();
-    type SuperSuffix = ();
+    type Suffix = String;
+    type SuperSuffix = Self;
 
     fn key(&self) -> Vec<Key<'_>> {
         match self {
             ContractType::Ft => vec![Key::Ref(b"ft")],
             ContractType::BlindBox => vec![Key::Ref(b"blind_box")],
             ContractType::Nft => vec![Key::Ref(b"nft")],
-            ContractType::Custom(name) => vec![Key::Ref(b"custom"), Key::Ref(name.as_bytes())],
+            ContractType::Registry => vec![Key::Ref(b"registry")],
+            ContractType::Custom(name) => vec![Key::Ref(CUSTOM_CONTRACT_NAMESPACE), Key::Ref(name.as_bytes())],
+        }
+    }
+}
+
+impl<'a> Prefixer<'a> for ContractType {
+    fn prefix(&self) -> Vec<Key<'_>> {
+        self.key()
+    }
+}
+
+impl KeyDeserialize for ContractType {
+    type Output = ContractType;
+
+    // 仅作为 Map 的完整主键反序列化；Custom 的两段键不支持出现在复合键的前部
+    const KEY_ELEMS: u16 = 1;
+
+    fn from_vec(value: Vec<u8>) -> StdResult<Self::Output> {
+        match value.as_slice() {
+            b"ft" => Ok(ContractType::Ft),
+            b"blind_box" => Ok(ContractType::BlindBox),
+            b"nft" => Ok(ContractType::Nft),
+            b"registry" => Ok(ContractType::Registry),
+            // 长度前缀（2 字节大端）+ "custom" + 名称
+            [len_hi, len_lo, rest @ ..]
+                if usize::from(u16::from_be_bytes([*len_hi, *len_lo])) == CUSTOM_CONTRACT_NAMESPACE.len()
+                    && rest.starts_with(CUSTOM_CONTRACT_NAMESPACE) =>
+            {
+                let name = String::from_vec(rest[CUSTOM_CONTRACT_NAMESPACE.len()..].to_vec())?;
+                Ok(ContractType::Custom(name))
+            }
+            _ => Err(StdError::parse_err("ContractType", "unknown contract type key")),
         }
     }
 }
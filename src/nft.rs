@@ -0,0 +1,134 @@
+use cosmwasm_std::{to_json_binary, Addr, CosmosMsg, Order, StdResult, Storage, Timestamp, WasmMsg};
+use cw_storage_plus::Bound;
+
+use crate::error::ContractError;
+use crate::msg::{ContractStatus, ContractType, Cw721ExecuteMsg, NftDelivery, NftReward, NftRewardSource};
+use crate::state::{reward_key, CONTRACTS, NFT_ESCROW};
+
+// ===== NFT 奖励 =====
+//
+// 两种来源：
+// - Mint: 领取时由注册的 `ContractType::Nft` 合约铸造，本合约需为该合约的 minter
+// - Escrow: 领取时转出本合约托管的 NFT。分发时即从托管中移出并记在奖励上，
+//   奖励作废或过期后退回托管，因此托管列表只包含可分配的 NFT。
+
+const TOKEN_ID_PLACEHOLDER: &str = "{id}";
+const USER_PLACEHOLDER: &str = "{user}";
+
+/// 铸造模板须包含 `{id}` 以保证 token_id 唯一
+pub fn validate_source(source: &NftRewardSource) -> Result<(), ContractError> {
+    let valid = match source {
+        NftRewardSource::Mint { token_id_template, .. } => token_id_template.contains(TOKEN_ID_PLACEHOLDER),
+        NftRewardSource::Escrow { collection, token_id } => {
+            !collection.is_empty() && token_id.as_ref().is_none_or(|token_id| !token_id.is_empty())
+        }
+    };
+    if !valid {
+        return Err(ContractError::InvalidConfiguration {});
+    }
+    Ok(())
+}
+
+/// 为奖励确定 NFT：铸造来源生成 token_id，托管来源从托管中取出一枚
+pub fn resolve(
+    storage: &mut dyn Storage,
+    source: &NftRewardSource,
+    reward_id: &str,
+    user: &str,
+) -> Result<NftReward, ContractError> {
+    validate_source(source)?;
+    match source {
+        NftRewardSource::Mint { token_id_template, token_uri, extension } => {
+            let nft = CONTRACTS
+                .may_load(storage, ContractType::Nft)?
+                .ok_or(ContractError::ContractNotFound {})?;
+            if nft.status != ContractStatus::Active {
+                return Err(ContractError::ContractNotActive {});
+            }
+            let id = reward_key(reward_id).ok_or(ContractError::RewardNotFound {})?;
+            Ok(NftReward {
+                collection: nft.contract_addr,
+                token_id: token_id_template
+                    .replace(TOKEN_ID_PLACEHOLDER, &id.to_string())
+                    .replace(USER_PLACEHOLDER, user),
+                delivery: NftDelivery::Mint { token_uri: token_uri.clone(), extension: extension.clone() },
+            })
+        }
+        NftRewardSource::Escrow { collection, token_id } => {
+            let token_id = match token_id {
+                Some(token_id) => token_id.clone(),
+                None => NFT_ESCROW
+                    .prefix(collection.clone())
+                    .keys(storage, None, None, Order::Ascending)
+                    .next()
+                    .transpose()?
+                    .ok_or(ContractError::NftNotAvailable {})?,
+            };
+            withdraw(storage, collection, &token_id)?;
+            Ok(NftReward {
+                collection: Addr::unchecked(collection),
+                token_id,
+                delivery: NftDelivery::Transfer,
+            })
+        }
+    }
+}
+
+/// NFT 入库
+pub fn deposit(storage: &mut dyn Storage, collection: &Addr, token_id: &str, now: Timestamp) -> StdResult<()> {
+    NFT_ESCROW.save(storage, (collection.to_string(), token_id.to_string()), &now)
+}
+
+/// 从托管中移出一枚可分配的 NFT
+pub fn withdraw(storage: &mut dyn Storage, collection: &str, token_id: &str) -> Result<(), ContractError> {
+    let key = (collection.to_string(), token_id.to_string());
+    if !NFT_ESCROW.has(storage, key.clone()) {
+        return Err(ContractError::NftNotAvailable {});
+    }
+    NFT_ESCROW.remove(storage, key);
+    Ok(())
+}
+
+/// 奖励作废或过期后，托管来源的 NFT 退回托管；铸造来源无需处理
+pub fn release(storage: &mut dyn Storage, nft: &NftReward, now: Timestamp) -> StdResult<()> {
+    match nft.delivery {
+        NftDelivery::Transfer => deposit(storage, &nft.collection, &nft.token_id, now),
+        NftDelivery::Mint { .. } => Ok(()),
+    }
+}
+
+/// 领取时把 NFT 发给用户
+pub fn payout_msg(nft: &NftReward, recipient: &str) -> StdResult<CosmosMsg> {
+    let msg = match &nft.delivery {
+        NftDelivery::Mint { token_uri, extension } => Cw721ExecuteMsg::Mint {
+            token_id: nft.token_id.clone(),
+            owner: recipient.to_string(),
+            token_uri: token_uri.clone(),
+            extension: extension.clone(),
+        },
+        NftDelivery::Transfer => Cw721ExecuteMsg::TransferNft {
+            recipient: recipient.to_string(),
+            token_id: nft.token_id.clone(),
+        },
+    };
+    Ok(WasmMsg::Execute {
+        contract_addr: nft.collection.to_string(),
+        msg: to_json_binary(&msg)?,
+        funds: vec![],
+    }
+    .into())
+}
+
+/// 某合约下可分配的托管 NFT
+pub fn escrowed(
+    storage: &dyn Storage,
+    collection: &str,
+    start_after: Option<String>,
+    limit: usize,
+) -> StdResult<Vec<String>> {
+    NFT_ESCROW
+        .prefix(collection.to_string())
+        .keys(storage, start_after.map(Bound::exclusive), None, Order::Ascending)
+        .take(limit)
+        .collect()
+}
@@ -0,0 +1,64 @@
+use cosmwasm_std::{Order, StdResult, Storage, Timestamp};
+
+use crate::error::ContractError;
+use crate::msg::ReferralNode;
+use crate::state::{REFERRALS, REFERRERS};
+
+/// 推荐链最大深度（直推、二级、三级）
+pub const MAX_REFERRAL_DEPTH: u32 = 3;
+
+/// 绑定推荐人：每个用户只能绑定一次，且不能形成环
+pub fn bind(storage: &mut dyn Storage, user: &str, referrer: &str, now: Timestamp) -> Result<(), ContractError> {
+    if REFERRERS.has(storage, user.to_string()) {
+        return Err(ContractError::ReferrerAlreadyBound {});
+    }
+    if user == referrer {
+        return Err(ContractError::InvalidReferrer {});
+    }
+
+    // 推荐人的上线中不能出现当前用户；已有的链无环，因此遍历必然终止
+    let mut current = referrer.to_string();
+    while let Some(next) = REFERRERS.may_load(storage, current)? {
+        if next == user {
+            return Err(ContractError::InvalidReferrer {});
+        }
+        current = next;
+    }
+
+    REFERRERS.save(storage, user.to_string(), &referrer.to_string())?;
+    REFERRALS.save(storage, (referrer.to_string(), user.to_string()), &now)?;
+    Ok(())
+}
+
+/// 自下而上的推荐链，最多 `depth` 层
+pub fn upline(storage: &dyn Storage, user: &str, depth: u32) -> StdResult<Vec<ReferralNode>> {
+    let mut nodes = vec![];
+    let mut current = user.to_string();
+    for level in 1..=depth {
+        match REFERRERS.may_load(storage, current)? {
+            Some(referrer) => {
+                nodes.push(ReferralNode { user: referrer.clone(), depth: level });
+                current = referrer;
+            }
+            None => break,
+        }
+    }
+    Ok(nodes)
+}
+
+/// 按层级展开的下线，最多 `depth` 层
+pub fn downline(storage: &dyn Storage, user: &str, depth: u32) -> StdResult<Vec<ReferralNode>> {
+    let mut nodes = vec![];
+    let mut frontier = vec![user.to_string()];
+    for level in 1..=depth {
+        let mut next = vec![];
+        for referrer in &frontier {
+            for referee in REFERRALS.prefix(referrer.clone()).keys(storage, None, None, Order::Ascending) {
+                next.push(referee?);
+            }
+        }
+        nodes.extend(next.iter().map(|referee| ReferralNode { user: referee.clone(), depth: level }));
+        frontier = next;
+    }
+    Ok(nodes)
+}
@@ -0,0 +1,172 @@
+use cosmwasm_std::{Timestamp, Uint128};
+
+use crate::error::ContractError;
+use crate::msg::{
+    ActivityType, ConditionOperator, ConditionType, RewardDefinition, RewardType, RuleCondition, RuleDetails,
+    UserLevel,
+};
+
+// ===== 规则评估引擎 =====
+//
+// 条件值均以字符串存储，按条件类型解释：
+// - ActivityType: 活动名称（见 `activity_type_name`），`In` 为逗号分隔列表
+// - UserLevel: 等级名称（如 "Gold"），支持大小比较，`In` 为逗号分隔列表
+// - TimeRange: 区块时间（秒），`In` 为闭区间 "start,end"
+// - Amount: 上报的活动数值，`In` 为闭区间 "min,max"
+// - Custom: 合约内无法评估，始终不匹配
+
+/// 一次活动上报的评估上下文
+pub struct ActivityContext<'a> {
+    pub activity_type: &'a ActivityType,
+    pub user_level: &'a UserLevel,
+    pub value: Uint128,
+    pub now: Timestamp,
+}
+
+/// 规则条件中使用的活动名称
+pub fn activity_type_name(activity_type: &ActivityType) -> String {
+    match activity_type {
+        ActivityType::BlindBoxOpen { .. } => "blind_box_open".to_string(),
+        ActivityType::NftExchange { .. } => "nft_exchange".to_string(),
+        ActivityType::Referral { .. } => "referral_reward".to_string(),
+        ActivityType::LevelUp { .. } => "level_up".to_string(),
+        ActivityType::Custom { activity_id } => activity_id.clone(),
+    }
+}
+
+pub fn parse_user_level(value: &str) -> Option<UserLevel> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "bronze" => Some(UserLevel::Bronze),
+        "silver" => Some(UserLevel::Silver),
+        "gold" => Some(UserLevel::Gold),
+        "platinum" => Some(UserLevel::Platinum),
+        "diamond" => Some(UserLevel::Diamond),
+        "master" => Some(UserLevel::Master),
+        "grandmaster" | "grand_master" => Some(UserLevel::GrandMaster),
+        _ => None,
+    }
+}
+
+/// 规则启用且所有条件均满足时匹配
+pub fn rule_matches(rule: &RuleDetails, ctx: &ActivityContext) -> bool {
+    rule.enabled && rule.conditions.iter().all(|condition| condition_matches(condition, ctx))
+}
+
+/// 奖励金额 = amount * multiplier（向下取整）
+pub fn reward_amount(reward: &RewardDefinition) -> Uint128 {
+    reward.amount.mul_floor(reward.multiplier)
+}
+
+/// 在保存前校验规则，确保评估时每个条件值都可解析
+pub fn validate_rule(rule: &RuleDetails) -> Result<(), ContractError> {
+    if rule.rewards.is_empty() {
+        return Err(ContractError::InvalidConfiguration {});
+    }
+    // NFT 奖励必须指定来源，其他类型不得指定
+    for reward in &rule.rewards {
+        // 奖励级条件不参与评估，条件须设置在规则上
+        if !reward.conditions.is_empty() {
+            return Err(ContractError::InvalidConfiguration {});
+        }
+        match (&reward.reward_type, &reward.nft) {
+            (RewardType::Nft, Some(source)) => crate::nft::validate_source(source)?,
+            (RewardType::Nft, None) | (_, Some(_)) => return Err(ContractError::InvalidConfiguration {}),
+            _ => {}
+        }
+        // 只有代币奖励可以分期解锁
+        match (&reward.reward_type, &reward.vesting) {
+            (RewardType::Token, Some(schedule)) => crate::vesting::validate_schedule(schedule)?,
+            (_, Some(_)) => return Err(ContractError::InvalidConfiguration {}),
+            _ => {}
+        }
+    }
+    for condition in &rule.conditions {
+        if !condition_is_valid(condition) {
+            return Err(ContractError::InvalidConfiguration {});
+        }
+    }
+    Ok(())
+}
+
+fn condition_is_valid(condition: &RuleCondition) -> bool {
+    let value = condition.value.as_str();
+    match (&condition.condition_type, &condition.operator) {
+        (ConditionType::ActivityType, ConditionOperator::GreaterThan | ConditionOperator::LessThan) => false,
+        (ConditionType::ActivityType, _) => !value.is_empty(),
+        (ConditionType::UserLevel, ConditionOperator::Contains) => false,
+        (ConditionType::UserLevel, ConditionOperator::In) => split_list(value).all(|v| parse_user_level(v).is_some()),
+        (ConditionType::UserLevel, _) => parse_user_level(value).is_some(),
+        (ConditionType::TimeRange | ConditionType::Amount, ConditionOperator::Contains) => false,
+        (ConditionType::TimeRange | ConditionType::Amount, ConditionOperator::In) => parse_range(value).is_some(),
+        (ConditionType::TimeRange | ConditionType::Amount, _) => parse_number(value).is_some(),
+        (ConditionType::Custom, _) => true,
+    }
+}
+
+fn condition_matches(condition: &RuleCondition, ctx: &ActivityContext) -> bool {
+    let value = condition.value.as_str();
+    match condition.condition_type {
+        ConditionType::ActivityType => {
+            let name = activity_type_name(ctx.activity_type);
+            match condition.operator {
+                ConditionOperator::Equals => name == value,
+                ConditionOperator::NotEquals => name != value,
+                ConditionOperator::Contains => name.contains(value),
+                ConditionOperator::In => split_list(value).any(|v| v == name),
+                ConditionOperator::GreaterThan | ConditionOperator::LessThan => false,
+            }
+        }
+        ConditionType::UserLevel => {
+            let actual = ctx.user_level.rank();
+            match condition.operator {
+                ConditionOperator::In => split_list(value).filter_map(parse_user_level).any(|l| l.rank() == actual),
+                ConditionOperator::Contains => false,
+                _ => match parse_user_level(value) {
+                    Some(expected) => compare(&condition.operator, actual, expected.rank()),
+                    None => false,
+                },
+            }
+        }
+        ConditionType::TimeRange => numeric_matches(&condition.operator, value, u128::from(ctx.now.seconds())),
+        ConditionType::Amount => numeric_matches(&condition.operator, value, ctx.value.u128()),
+        ConditionType::Custom => false,
+    }
+}
+
+fn numeric_matches(operator: &ConditionOperator, value: &str, actual: u128) -> bool {
+    match operator {
+        ConditionOperator::In => match parse_range(value) {
+            Some((min, max)) => actual >= min && actual <= max,
+            None => false,
+        },
+        ConditionOperator::Contains => false,
+        _ => match parse_number(value) {
+            Some(expected) => compare(operator, actual, expected),
+            None => false,
+        },
+    }
+}
+
+fn compare<T: PartialOrd>(operator: &ConditionOperator, actual: T, expected: T) -> bool {
+    match operator {
+        ConditionOperator::Equals => actual == expected,
+        ConditionOperator::NotEquals => actual != expected,
+        ConditionOperator::GreaterThan => actual > expected,
+        ConditionOperator::LessThan => actual < expected,
+        ConditionOperator::Contains | ConditionOperator::In => false,
+    }
+}
+
+fn split_list(value: &str) -> impl Iterator<Item = &str> {
+    value.split(',').map(str::trim).filter(|v| !v.is_empty())
+}
+
+fn parse_number(value: &str) -> Option<u128> {
+    value.trim().parse().ok()
+}
+
+fn parse_range(value: &str) -> Option<(u128, u128)> {
+    let (min, max) = value.split_once(',')?;
+    let (min, max) = (parse_number(min)?, parse_number(max)?);
+    (min <= max).then_some((min, max))
+}
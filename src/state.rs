@@ -1,17 +1,34 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Uint128, Timestamp, Storage};
-use cw_storage_plus::{Item, Map};
+
+use cosmwasm_std::{Addr, StdResult, Uint128, Timestamp, Storage};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex};
 use crate::msg::*;
 
 // ===== 配置存储 =====
 
 pub const CONFIG: Item<IncentiveConfig> = Item::new("config");
 pub const ADMIN: Item<Addr> = Item::new("admin");
+/// 已提名、等待接受的新管理员
+pub const PENDING_ADMIN: Item<Addr> = Item::new("pending_admin");
+/// (`Role::key()`, 地址) -> 授予时间
+pub const ROLES: Map<(String, String), Timestamp> = Map::new("roles");
+/// `PauseScope::key()` -> 暂停记录
+pub const PAUSED: Map<&str, PausedScope> = Map::new("paused");
 
 // ===== 奖励存储 =====
 
-pub const USER_REWARDS: Map<String, Vec<UserReward>> = Map::new("user_rewards");
+/// 以奖励序号（`reward_{n}` 中的 n）为主键的奖励表
+pub const REWARDS: IndexedMap<u64, UserReward, RewardIndexes> = IndexedMap::new(
+    "rewards",
+    RewardIndexes {
+        user: MultiIndex::new(reward_user_index, "rewards", "rewards__user"),
+        status: MultiIndex::new(reward_status_index, "rewards", "rewards__status"),
+        activity: MultiIndex::new(reward_activity_index, "rewards", "rewards__activity"),
+    },
+);
 pub const REWARD_COUNTER: Item<u64> = Item::new("reward_counter");
+/// (过期时间秒, 奖励ID) -> 用户，仅包含待领取且设置了过期时间的奖励
+pub const EXPIRY_QUEUE: Map<(u64, String), String> = Map::new("expiry_queue");
 
 // ===== 规则存储 =====
 
@@ -21,17 +38,104 @@ pub const RULE_COUNTER: Item<u64> = Item::new("rule_counter");
 // ===== 合约注册存储 =====
 
 pub const CONTRACTS: Map<ContractType, ContractInfo> = Map::new("contracts");
+/// 合约地址 -> 合约类型，用于识别调用者
+pub const CONTRACT_ADDRS: Map<String, ContractType> = Map::new("contract_addrs");
 
 // ===== 用户等级存储 =====
 
 pub const USER_LEVELS: Map<String, UserLevelInfo> = Map::new("user_levels");
 
+// ===== 积分兑换存储 =====
+
+/// (用户, 小时) -> 该小时内兑换的积分
+pub const EXCHANGE_USER_VOLUME: Map<(String, u64), Uint128> = Map::new("exchange_user_volume");
+/// 小时 -> 该小时内全体用户兑换的积分
+pub const EXCHANGE_GLOBAL_VOLUME: Map<u64, Uint128> = Map::new("exchange_global_volume");
+
+/// 外部事件ID -> 处理结果，用于分发去重
+pub const PROCESSED_EVENTS: Map<String, ProcessedEvent> = Map::new("processed_events");
+
+/// 关闭自动领取的用户 -> 设置时间
+pub const AUTO_CLAIM_OPT_OUTS: Map<String, Timestamp> = Map::new("auto_claim_opt_outs");
+
+// ===== 推荐关系存储 =====
+
+/// 用户 -> 推荐人
+pub const REFERRERS: Map<String, String> = Map::new("referrers");
+/// (推荐人, 被推荐人) -> 绑定时间，用于展开下线
+pub const REFERRALS: Map<(String, String), Timestamp> = Map::new("referrals");
+
+// ===== 金库存储 =====
+
+/// 以 `AssetInfo::key()` 为键的金库余额
+pub const TREASURY: Map<String, TreasuryBalance> = Map::new("treasury");
+/// (NFT 合约, token_id) -> 入库时间，仅包含尚未分配给奖励的托管 NFT
+pub const NFT_ESCROW: Map<(String, String), Timestamp> = Map::new("nft_escrow");
+/// 通过 Ft 合约累计铸造的代币数量
+pub const TOTAL_MINTED: Item<Uint128> = Item::new("total_minted");
+
 // ===== 统计存储 =====
 
 pub const STATS: Item<SystemStats> = Item::new("stats");
+/// 活动名称 -> 该活动创建的奖励统计
+pub const ACTIVITY_STATS: Map<String, RewardTotals> = Map::new("activity_stats");
+/// (用户, 状态) -> 该用户当前处于该状态的奖励统计，供奖励历史查询返回合计
+pub const USER_REWARD_TOTALS: Map<(String, String), RewardTotals> = Map::new("user_reward_totals");
+/// 已出现过的用户 -> 首次出现时间，用于统计用户数
+pub const KNOWN_USERS: Map<String, Timestamp> = Map::new("known_users");
 
 // ===== 数据结构 =====
 
+pub struct RewardIndexes<'a> {
+    /// 用户 -> 奖励
+    pub user: MultiIndex<'a, String, UserReward, u64>,
+    /// (用户, 状态) -> 奖励
+    pub status: MultiIndex<'a, (String, String), UserReward, u64>,
+    /// (用户, 活动名称) -> 奖励
+    pub activity: MultiIndex<'a, (String, String), UserReward, u64>,
+}
+
+impl IndexList<UserReward> for RewardIndexes<'_> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<UserReward>> + '_> {
+        let v: Vec<&dyn Index<UserReward>> = vec![&self.user, &self.status, &self.activity];
+        Box::new(v.into_iter())
+    }
+}
+
+fn reward_user_index(_pk: &[u8], reward: &UserReward) -> String {
+    reward.user.clone()
+}
+
+fn reward_status_index(_pk: &[u8], reward: &UserReward) -> (String, String) {
+    (reward.user.clone(), status_key(&reward.status))
+}
+
+fn reward_activity_index(_pk: &[u8], reward: &UserReward) -> (String, String) {
+    (reward.user.clone(), crate::rules::activity_type_name(&reward.activity_type))
+}
+
+/// 积分兑换铸币子消息的回调载荷
+#[cw_serde]
+pub struct PointsExchangePayload {
+    pub reward_id: String,
+    pub user: String,
+    pub points: Uint128,
+}
+
+/// 奖励铸币子消息的回调载荷
+#[cw_serde]
+pub struct MintPayload {
+    pub recipient: String,
+    pub amount: Uint128,
+}
+
+#[cw_serde]
+pub struct ProcessedEvent {
+    pub reward_ids: Vec<String>,
+    pub processed_by: String,
+    pub processed_at: Timestamp,
+}
+
 #[cw_serde]
 pub struct SystemStats {
     pub total_users: u32,
@@ -39,6 +143,60 @@ pub struct SystemStats {
     pub total_rules: u32,
     pub total_contracts: u32,
     pub last_updated: Timestamp,
+    /// 按奖励当前状态统计
+    pub status_totals: RewardStatusTotals,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct RewardTotals {
+    pub count: u64,
+    pub amount: Uint128,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct RewardStatusTotals {
+    pub pending: RewardTotals,
+    pub claimed: RewardTotals,
+    pub expired: RewardTotals,
+    pub cancelled: RewardTotals,
+}
+
+impl RewardStatusTotals {
+    pub fn get_mut(&mut self, status: &RewardStatus) -> &mut RewardTotals {
+        match status {
+            RewardStatus::Pending => &mut self.pending,
+            RewardStatus::Claimed => &mut self.claimed,
+            RewardStatus::Expired => &mut self.expired,
+            RewardStatus::Cancelled => &mut self.cancelled,
+        }
+    }
+}
+
+impl RewardTotals {
+    fn add(&mut self, amount: Uint128) {
+        self.count += 1;
+        self.amount += amount;
+    }
+
+    fn sub(&mut self, amount: Uint128) {
+        self.count = self.count.saturating_sub(1);
+        self.amount = self.amount.saturating_sub(amount);
+    }
+}
+
+impl SystemStats {
+    pub fn new(now: Timestamp) -> Self {
+        SystemStats {
+            total_users: 0,
+            total_rewards_distributed: Uint128::zero(),
+            total_rules: 0,
+            total_contracts: 0,
+            last_updated: now,
+            status_totals: RewardStatusTotals::default(),
+        }
+    }
 }
 
 // ===== 辅助函数 =====
@@ -49,8 +207,112 @@ pub fn get_next_reward_id(storage: &mut dyn Storage) -> Result<String, cosmwasm_
     Ok(format!("reward_{}", counter))
 }
 
+/// 奖励ID在 REWARDS 中的主键；无法解析的ID返回 None
+pub fn reward_key(reward_id: &str) -> Option<u64> {
+    reward_id.strip_prefix("reward_")?.parse().ok()
+}
+
+/// 状态索引使用的键
+pub fn status_key(status: &RewardStatus) -> String {
+    match status {
+        RewardStatus::Pending => "pending",
+        RewardStatus::Claimed => "claimed",
+        RewardStatus::Expired => "expired",
+        RewardStatus::Cancelled => "cancelled",
+    }
+    .to_string()
+}
+
+/// 按奖励ID读取奖励及其主键
+pub fn may_load_reward(storage: &dyn Storage, reward_id: &str) -> StdResult<Option<(u64, UserReward)>> {
+    let Some(key) = reward_key(reward_id) else {
+        return Ok(None);
+    };
+    Ok(REWARDS.may_load(storage, key)?.map(|reward| (key, reward)))
+}
+
 pub fn get_next_rule_id(storage: &mut dyn Storage) -> Result<String, cosmwasm_std::StdError> {
     let counter = RULE_COUNTER.may_load(storage)?.unwrap_or(0);
     RULE_COUNTER.save(storage, &(counter + 1))?;
     Ok(format!("rule_{}", counter))
 }
+
+/// 读取-修改-写回系统统计，并刷新更新时间
+pub fn update_stats<F>(storage: &mut dyn Storage, now: Timestamp, update: F) -> StdResult<SystemStats>
+where
+    F: FnOnce(&mut SystemStats),
+{
+    let mut stats = STATS.may_load(storage)?.unwrap_or_else(|| SystemStats::new(now));
+    update(&mut stats);
+    stats.last_updated = now;
+    STATS.save(storage, &stats)?;
+    Ok(stats)
+}
+
+/// 首次出现的用户计入用户总数
+pub fn track_user(storage: &mut dyn Storage, user: &str, now: Timestamp) -> StdResult<()> {
+    if KNOWN_USERS.has(storage, user.to_string()) {
+        return Ok(());
+    }
+    KNOWN_USERS.save(storage, user.to_string(), &now)?;
+    update_stats(storage, now, |stats| stats.total_users += 1)?;
+    Ok(())
+}
+
+/// 新奖励计入分发总额、活动维度与状态维度统计
+pub fn record_reward_created(storage: &mut dyn Storage, reward: &UserReward, now: Timestamp) -> StdResult<()> {
+    track_user(storage, &reward.user, now)?;
+    update_stats(storage, now, |stats| {
+        stats.total_rewards_distributed += reward.amount;
+        stats.status_totals.get_mut(&reward.status).add(reward.amount);
+    })?;
+    update_user_totals(storage, reward, &reward.status, RewardTotals::add)?;
+    let activity = crate::rules::activity_type_name(&reward.activity_type);
+    let mut totals = ACTIVITY_STATS.may_load(storage, activity.clone())?.unwrap_or_default();
+    totals.add(reward.amount);
+    ACTIVITY_STATS.save(storage, activity, &totals)?;
+    Ok(())
+}
+
+/// 奖励状态变化时在状态维度之间转移
+pub fn record_reward_status_change(
+    storage: &mut dyn Storage,
+    reward: &UserReward,
+    from: &RewardStatus,
+    now: Timestamp,
+) -> StdResult<()> {
+    update_stats(storage, now, |stats| {
+        stats.status_totals.get_mut(from).sub(reward.amount);
+        stats.status_totals.get_mut(&reward.status).add(reward.amount);
+    })?;
+    update_user_totals(storage, reward, from, RewardTotals::sub)?;
+    update_user_totals(storage, reward, &reward.status, RewardTotals::add)?;
+    Ok(())
+}
+
+fn update_user_totals(
+    storage: &mut dyn Storage,
+    reward: &UserReward,
+    status: &RewardStatus,
+    update: fn(&mut RewardTotals, Uint128),
+) -> StdResult<()> {
+    let key = (reward.user.clone(), status_key(status));
+    let mut totals = USER_REWARD_TOTALS.may_load(storage, key.clone())?.unwrap_or_default();
+    update(&mut totals, reward.amount);
+    USER_REWARD_TOTALS.save(storage, key, &totals)
+}
+
+/// 用户在指定状态（None 为全部状态）下的奖励合计
+pub fn user_reward_totals(storage: &dyn Storage, user: &str, status: Option<&RewardStatus>) -> StdResult<RewardTotals> {
+    if let Some(status) = status {
+        let totals = USER_REWARD_TOTALS.may_load(storage, (user.to_string(), status_key(status)))?;
+        return Ok(totals.unwrap_or_default());
+    }
+    let mut totals = RewardTotals::default();
+    for item in USER_REWARD_TOTALS.prefix(user.to_string()).range(storage, None, None, cosmwasm_std::Order::Ascending) {
+        let (_, status_totals) = item?;
+        totals.count += status_totals.count;
+        totals.amount += status_totals.amount;
+    }
+    Ok(totals)
+}
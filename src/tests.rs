@@ -1,9 +1,120 @@
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod tests {
-    use cosmwasm_std::testing::{mock_dependencies, mock_env, message_info};
-    use cosmwasm_std::{coins, from_json, Uint128, Addr};
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, message_info, MockApi, MockQuerier, MockStorage};
+    use cosmwasm_std::{
+        attr, coins, from_json, to_json_binary, Addr, BankMsg, CosmosMsg, Decimal, Deps, DepsMut, OwnedDeps, Reply, ReplyOn,
+        SubMsgResult, Timestamp, Uint128, WasmMsg,
+    };
+    use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+    use crate::error::ContractError;
     use crate::msg::*;
-    use crate::contract::{instantiate, execute, query};
+    use crate::state::{RewardTotals, SystemStats};
+    use crate::contract::{instantiate, execute, query, migrate, reply};
+
+    const USER: &str = "luckee1user123456789012345678901234567890";
+
+    fn mock_config() -> IncentiveConfig {
+        IncentiveConfig {
+            max_rewards_per_user: 1000,
+            reward_expiration_days: 30,
+            auto_claim_enabled: false,
+            manual_claim_reward_types: vec![RewardType::Nft],
+            payout_method: PayoutMethod::Native { denom: "uluckee".to_string() },
+            mint_cap: Uint128::zero(),
+            referral_rates: vec![Decimal::percent(50), Decimal::percent(20), Decimal::percent(10)],
+            level_thresholds: vec![
+                LevelThreshold { level: UserLevel::Bronze, min_points: 0 },
+                LevelThreshold { level: UserLevel::Silver, min_points: 1000 },
+                LevelThreshold { level: UserLevel::Gold, min_points: 5000 },
+                LevelThreshold { level: UserLevel::Platinum, min_points: 20000 },
+            ],
+            level_demotion_enabled: false,
+            level_up_reward: Uint128::zero(),
+            level_multipliers: vec![
+                LevelMultiplier { level: UserLevel::Silver, multiplier: Decimal::percent(120) },
+                LevelMultiplier { level: UserLevel::Gold, multiplier: Decimal::percent(150) },
+                LevelMultiplier { level: UserLevel::Platinum, multiplier: Decimal::percent(200) },
+            ],
+            max_batch_size: 100,
+            points_exchange: PointsExchangeConfig {
+                rate: Decimal::one(),
+                min_points: Uint128::from(1000u128),
+                max_points: Uint128::zero(),
+                user_daily_cap: Uint128::zero(),
+                global_daily_cap: Uint128::zero(),
+            },
+        }
+    }
+
+    fn fund_treasury(deps: DepsMut, amount: u128) {
+        let sponsor = Addr::unchecked("sponsor");
+        let info = message_info(&sponsor, &coins(amount, "uluckee"));
+        execute(deps, mock_env(), info, ExecuteMsg::FundTreasury {}).unwrap();
+    }
+
+    fn mock_rule(conditions: Vec<RuleCondition>, amount: u128, multiplier: Decimal) -> RuleDetails {
+        RuleDetails {
+            rule_id: String::new(),
+            rule_name: "test rule".to_string(),
+            rule_type: RuleType::ActivityBased,
+            conditions,
+            rewards: vec![RewardDefinition {
+                reward_type: RewardType::Token,
+                amount: Uint128::from(amount),
+                multiplier,
+                conditions: vec![],
+                nft: None,
+                vesting: None,
+            }],
+            enabled: true,
+            created_at: Timestamp::from_seconds(0),
+            updated_at: Timestamp::from_seconds(0),
+        }
+    }
+
+    /// 注册 Ft 与 Registry 合约，返回两者地址
+    fn setup_points_exchange(
+        deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
+        config: IncentiveConfig,
+    ) -> (Addr, Addr) {
+        let creator = Addr::unchecked("creator");
+        let info = message_info(&creator, &[]);
+        instantiate(deps.as_mut(), mock_env(), info.clone(), InstantiateMsg { admin: None, config }).unwrap();
+
+        let ft_addr = deps.api.addr_make("ft");
+        let registry_addr = deps.api.addr_make("registry");
+        let registrations = [
+            (ContractType::Ft, &ft_addr, vec![]),
+            (ContractType::Registry, &registry_addr, vec![CAPABILITY_QUERY_POINTS.to_string()]),
+        ];
+        for (contract_type, addr, capabilities) in registrations {
+            execute(deps.as_mut(), mock_env(), info.clone(), ExecuteMsg::RegisterContract {
+                contract_type,
+                contract_addr: addr.to_string(),
+                capabilities,
+            }).unwrap();
+        }
+        (ft_addr, registry_addr)
+    }
+
+    /// 模拟 DD Registry：任何用户都有 `points` 积分
+    fn registry_points(points: u128) -> impl Fn(&cosmwasm_std::WasmQuery) -> cosmwasm_std::QuerierResult {
+        move |query| match query {
+            cosmwasm_std::WasmQuery::Smart { msg, .. } => {
+                let RegistryQueryMsg::UserPoints { user } = from_json(msg).unwrap();
+                let res = UserPointsResponse { user, points: Uint128::from(points) };
+                cosmwasm_std::SystemResult::Ok(cosmwasm_std::ContractResult::Ok(to_json_binary(&res).unwrap()))
+            }
+            _ => panic!("unexpected query"),
+        }
+    }
+
+    fn query_treasury(deps: Deps) -> Vec<TreasuryAssetBalance> {
+        let res = query(deps, mock_env(), QueryMsg::TreasuryBalance {}).unwrap();
+        let res: TreasuryBalanceResponse = from_json(&res).unwrap();
+        res.balances
+    }
 
     #[test]
     fn test_instantiate() {
@@ -14,11 +125,7 @@ mod tests {
 
         let msg = InstantiateMsg {
             admin: None, // 使用默认管理员（调用者）
-            config: IncentiveConfig {
-                max_rewards_per_user: 1000,
-                reward_expiration_days: 30,
-                auto_claim_enabled: true,
-            },
+            config: mock_config(),
         };
 
         let res = instantiate(deps.as_mut(), env, info, msg).unwrap();
@@ -34,11 +141,7 @@ mod tests {
 
         let msg = InstantiateMsg {
             admin: None, // 使用默认管理员（调用者）
-            config: IncentiveConfig {
-                max_rewards_per_user: 1000,
-                reward_expiration_days: 30,
-                auto_claim_enabled: true,
-            },
+            config: mock_config(),
         };
 
         instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
@@ -58,17 +161,16 @@ mod tests {
         // 实例化
         let msg = InstantiateMsg {
             admin: None, // 使用默认管理员（调用者）
-            config: IncentiveConfig {
-                max_rewards_per_user: 1000,
-                reward_expiration_days: 30,
-                auto_claim_enabled: true,
-            },
+            config: mock_config(),
         };
 
         instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
 
+        // 注资金库
+        fund_treasury(deps.as_mut(), 1000);
+
         // 分发奖励
-        let admin_info = message_info(&creator, &coins(1000, "earth"));
+        let admin_info = message_info(&creator, &[]);
         let execute_msg = ExecuteMsg::DistributeReward {
             user: "luckee1user123456789012345678901234567890".to_string(),
             amount: Uint128::from(1000u128),
@@ -76,6 +178,7 @@ mod tests {
                 nft_kind: "rare".to_string(),
                 box_id: "box1".to_string(),
             },
+            event_id: None,
         };
 
         let res = execute(deps.as_mut(), env.clone(), admin_info, execute_msg).unwrap();
@@ -92,18 +195,14 @@ mod tests {
         // 实例化
         let msg = InstantiateMsg {
             admin: None, // 使用默认管理员（调用者）
-            config: IncentiveConfig {
-                max_rewards_per_user: 1000,
-                reward_expiration_days: 30,
-                auto_claim_enabled: true,
-            },
+            config: mock_config(),
         };
 
         instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
 
         // 尝试未授权操作
         let user = Addr::unchecked("user");
-        let user_info = message_info(&user, &coins(1000, "earth"));
+        let user_info = message_info(&user, &[]);
         let execute_msg = ExecuteMsg::DistributeReward {
             user: "luckee1user223456789012345678901234567890".to_string(),
             amount: Uint128::from(1000u128),
@@ -111,9 +210,2028 @@ mod tests {
                 nft_kind: "rare".to_string(),
                 box_id: "box1".to_string(),
             },
+            event_id: None,
         };
 
         let res = execute(deps.as_mut(), env, user_info, execute_msg);
         assert!(res.is_err());
     }
+
+    #[test]
+    fn test_claim_reward_persists_and_pays_out() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let creator = Addr::unchecked("creator");
+        let info = message_info(&creator, &[]);
+
+        let msg = InstantiateMsg { admin: None, config: mock_config() };
+        instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        fund_treasury(deps.as_mut(), 500);
+
+        let execute_msg = ExecuteMsg::DistributeReward {
+            user: USER.to_string(),
+            amount: Uint128::from(500u128),
+            activity_type: ActivityType::Custom { activity_id: "test".to_string() },
+            event_id: None,
+        };
+        execute(deps.as_mut(), env.clone(), info, execute_msg).unwrap();
+
+        // 领取奖励并发出原生代币转账
+        let user_info = message_info(&Addr::unchecked(USER), &[]);
+        let claim_msg = ExecuteMsg::ClaimReward { reward_id: "reward_0".to_string() };
+        let res = execute(deps.as_mut(), env.clone(), user_info.clone(), claim_msg.clone()).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: USER.to_string(),
+                amount: coins(500, "uluckee"),
+            })
+        );
+
+        // 状态已持久化
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::UserRewards { user: USER.to_string() }).unwrap();
+        let rewards: Vec<UserReward> = from_json(&res).unwrap();
+        assert_eq!(rewards[0].status, RewardStatus::Claimed);
+        assert_eq!(rewards[0].claimed_at, Some(env.block.time));
+
+        // 重复领取被拒绝
+        let err = execute(deps.as_mut(), env, user_info, claim_msg).unwrap_err();
+        assert_eq!(err, ContractError::RewardAlreadyClaimed {});
+    }
+
+    #[test]
+    fn test_claim_reward_mints_through_ft_contract() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let creator = Addr::unchecked("creator");
+        let info = message_info(&creator, &[]);
+
+        let mut config = mock_config();
+        config.payout_method = PayoutMethod::Cw20Mint;
+        let msg = InstantiateMsg { admin: None, config };
+        instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let ft_addr = deps.api.addr_make("ft");
+        execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::RegisterContract {
+            contract_type: ContractType::Ft,
+            contract_addr: ft_addr.to_string(),
+            capabilities: vec![],
+        }).unwrap();
+        execute(deps.as_mut(), env.clone(), info, ExecuteMsg::DistributeReward {
+            user: USER.to_string(),
+            amount: Uint128::from(700u128),
+            activity_type: ActivityType::Custom { activity_id: "test".to_string() },
+            event_id: None,
+        }).unwrap();
+
+        let user_info = message_info(&Addr::unchecked(USER), &[]);
+        let res = execute(deps.as_mut(), env, user_info, ExecuteMsg::ClaimReward {
+            reward_id: "reward_0".to_string(),
+        }).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: ft_addr.to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Mint {
+                    recipient: USER.to_string(),
+                    amount: Uint128::from(700u128),
+                }).unwrap(),
+                funds: vec![],
+            })
+        );
+        assert_eq!(res.messages[0].reply_on, ReplyOn::Error);
+
+        // 铸币失败时回调返回明确的错误，使整笔领取回滚
+        let err = reply(deps.as_mut(), mock_env(), Reply {
+            id: res.messages[0].id,
+            payload: res.messages[0].payload.clone(),
+            gas_used: 0,
+            result: SubMsgResult::Err("Unauthorized".to_string()),
+        }).unwrap_err();
+        assert!(matches!(err, ContractError::MintFailed { .. }));
+    }
+
+    #[test]
+    fn test_treasury_commits_and_settles_rewards() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let creator = Addr::unchecked("creator");
+        let info = message_info(&creator, &[]);
+
+        let msg = InstantiateMsg { admin: None, config: mock_config() };
+        instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // 金库为空时无法分发
+        let distribute = ExecuteMsg::DistributeReward {
+            user: USER.to_string(),
+            amount: Uint128::from(300u128),
+            activity_type: ActivityType::Custom { activity_id: "test".to_string() },
+            event_id: None,
+        };
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), distribute.clone()).unwrap_err();
+        assert_eq!(err, ContractError::InsufficientBalance {});
+
+        fund_treasury(deps.as_mut(), 500);
+        deps.querier.bank.update_balance(&env.contract.address, coins(500, "uluckee"));
+        execute(deps.as_mut(), env.clone(), info.clone(), distribute.clone()).unwrap();
+
+        let balances = query_treasury(deps.as_ref());
+        assert_eq!(balances[0].asset, AssetInfo::Native { denom: "uluckee".to_string() });
+        assert_eq!(balances[0].available, Uint128::from(200u128));
+        assert_eq!(balances[0].committed, Uint128::from(300u128));
+        assert_eq!(balances[0].held, Some(Uint128::from(500u128)));
+
+        // 只有 FundTreasury 接受资金，其他消息附带资金会被拒绝
+        let err = execute(deps.as_mut(), env.clone(), message_info(&creator, &coins(100, "uluckee")), distribute.clone())
+            .unwrap_err();
+        assert_eq!(err, ContractError::Payment(cw_utils::PaymentError::NonPayable {}));
+
+        // 剩余可用余额不足以覆盖第二笔奖励
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), distribute).unwrap_err();
+        assert_eq!(err, ContractError::InsufficientBalance {});
+
+        // 已锁定部分不能被提取
+        let withdraw = ExecuteMsg::WithdrawTreasury {
+            asset: AssetInfo::Native { denom: "uluckee".to_string() },
+            amount: Uint128::from(201u128),
+            recipient: None,
+        };
+        let err = execute(deps.as_mut(), env.clone(), info, withdraw).unwrap_err();
+        assert_eq!(err, ContractError::InsufficientBalance {});
+
+        // 领取后锁定余额转出
+        let user_info = message_info(&Addr::unchecked(USER), &[]);
+        execute(deps.as_mut(), env, user_info, ExecuteMsg::ClaimReward {
+            reward_id: "reward_0".to_string(),
+        }).unwrap();
+        let balances = query_treasury(deps.as_ref());
+        assert_eq!(balances[0].available, Uint128::from(200u128));
+        assert_eq!(balances[0].committed, Uint128::zero());
+    }
+
+    #[test]
+    fn test_cw20_treasury_funding() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let creator = Addr::unchecked("creator");
+        let info = message_info(&creator, &[]);
+
+        let mut config = mock_config();
+        config.payout_method = PayoutMethod::Cw20Transfer;
+        let msg = InstantiateMsg { admin: None, config };
+        instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let ft_addr = deps.api.addr_make("ft");
+        execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::RegisterContract {
+            contract_type: ContractType::Ft,
+            contract_addr: ft_addr.to_string(),
+            capabilities: vec![],
+        }).unwrap();
+
+        // CW20 合约通过 Receive 钩子注资
+        let receive = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "sponsor".to_string(),
+            amount: Uint128::from(1000u128),
+            msg: to_json_binary(&ReceiveMsg::FundTreasury {}).unwrap(),
+        });
+        execute(deps.as_mut(), env.clone(), message_info(&ft_addr, &[]), receive.clone()).unwrap();
+
+        // 未注册为 Ft 的合约不能入库
+        let attacker = deps.api.addr_make("attacker");
+        let err = execute(deps.as_mut(), env.clone(), message_info(&attacker, &[]), receive).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        execute(deps.as_mut(), env.clone(), info, ExecuteMsg::DistributeReward {
+            user: USER.to_string(),
+            amount: Uint128::from(400u128),
+            activity_type: ActivityType::Custom { activity_id: "test".to_string() },
+            event_id: None,
+        }).unwrap();
+
+        // 实际持有量通过 CW20 Balance 查询；已存在的无效资产查询失败时只影响自身
+        let broken = AssetInfo::Cw20 { contract_addr: Addr::unchecked("broken_token") };
+        crate::state::TREASURY.save(deps.as_mut().storage, broken.key(), &TreasuryBalance {
+            asset: broken.clone(),
+            available: Uint128::one(),
+            committed: Uint128::zero(),
+        }).unwrap();
+        let ft = ft_addr.to_string();
+        deps.querier.update_wasm(move |query| match query {
+            cosmwasm_std::WasmQuery::Smart { contract_addr, msg } if *contract_addr == ft => {
+                let cw20::Cw20QueryMsg::Balance { .. } = from_json(msg).unwrap() else {
+                    panic!("unexpected query");
+                };
+                let res = cw20::BalanceResponse { balance: Uint128::from(1000u128) };
+                cosmwasm_std::SystemResult::Ok(cosmwasm_std::ContractResult::Ok(to_json_binary(&res).unwrap()))
+            }
+            cosmwasm_std::WasmQuery::Smart { contract_addr, .. } => {
+                cosmwasm_std::SystemResult::Err(cosmwasm_std::SystemError::NoSuchContract {
+                    addr: contract_addr.clone(),
+                })
+            }
+            _ => panic!("unexpected query"),
+        });
+        let balances = query_treasury(deps.as_ref());
+        let held: Vec<_> = balances.iter().map(|balance| (balance.asset.clone(), balance.held)).collect();
+        assert!(held.contains(&(broken, None)));
+        let ft_balance = balances
+            .iter()
+            .find(|balance| balance.asset == AssetInfo::Cw20 { contract_addr: ft_addr.clone() })
+            .unwrap();
+        assert_eq!(ft_balance.available, Uint128::from(600u128));
+        assert_eq!(ft_balance.committed, Uint128::from(400u128));
+        assert_eq!(ft_balance.held, Some(Uint128::from(1000u128)));
+
+        // 领取时从金库转出 CW20
+        let user_info = message_info(&Addr::unchecked(USER), &[]);
+        let res = execute(deps.as_mut(), env, user_info, ExecuteMsg::ClaimReward {
+            reward_id: "reward_0".to_string(),
+        }).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: ft_addr.to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: USER.to_string(),
+                    amount: Uint128::from(400u128),
+                }).unwrap(),
+                funds: vec![],
+            })
+        );
+    }
+
+    #[test]
+    fn test_report_activity_evaluates_rules() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let creator = Addr::unchecked("creator");
+        let info = message_info(&creator, &[]);
+
+        let msg = InstantiateMsg { admin: None, config: mock_config() };
+        instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        fund_treasury(deps.as_mut(), 10_000);
+
+        // 盲盒开启且数值 >= 100 时奖励 1000 * 1.5
+        let rule = mock_rule(
+            vec![
+                RuleCondition {
+                    condition_type: ConditionType::ActivityType,
+                    operator: ConditionOperator::In,
+                    value: "blind_box_open,nft_exchange".to_string(),
+                },
+                RuleCondition {
+                    condition_type: ConditionType::Amount,
+                    operator: ConditionOperator::GreaterThan,
+                    value: "99".to_string(),
+                },
+                RuleCondition {
+                    condition_type: ConditionType::UserLevel,
+                    operator: ConditionOperator::LessThan,
+                    value: "Gold".to_string(),
+                },
+            ],
+            1000,
+            Decimal::percent(150),
+        );
+        execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::CreateRule { rule }).unwrap();
+
+        // 禁用的规则不参与评估
+        let mut disabled = mock_rule(vec![], 5000, Decimal::one());
+        disabled.enabled = false;
+        execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::CreateRule { rule: disabled }).unwrap();
+
+        let blind_box = ActivityType::BlindBoxOpen { nft_kind: "rare".to_string(), box_id: "box1".to_string() };
+
+        // 数值不满足条件，不产生奖励
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::ReportActivity {
+            user: USER.to_string(),
+            activity_type: blind_box.clone(),
+            value: Uint128::from(50u128),
+            event_id: None,
+        }).unwrap();
+        assert!(res.attributes.iter().any(|a| a.key == "rules_matched" && a.value == "0"));
+
+        let res = execute(deps.as_mut(), env.clone(), info, ExecuteMsg::ReportActivity {
+            user: USER.to_string(),
+            activity_type: blind_box.clone(),
+            value: Uint128::from(100u128),
+            event_id: None,
+        }).unwrap();
+        assert!(res.attributes.iter().any(|a| a.key == "total_amount" && a.value == "1500"));
+
+        let res = query(deps.as_ref(), env, QueryMsg::UserRewards { user: USER.to_string() }).unwrap();
+        let rewards: Vec<UserReward> = from_json(&res).unwrap();
+        assert_eq!(rewards.len(), 1);
+        assert_eq!(rewards[0].amount, Uint128::from(1500u128));
+        assert_eq!(rewards[0].activity_type, blind_box);
+    }
+
+    #[test]
+    fn test_create_rule_rejects_unparsable_conditions() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let creator = Addr::unchecked("creator");
+        let info = message_info(&creator, &[]);
+
+        let msg = InstantiateMsg { admin: None, config: mock_config() };
+        instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let rule = mock_rule(
+            vec![RuleCondition {
+                condition_type: ConditionType::UserLevel,
+                operator: ConditionOperator::Equals,
+                value: "Legend".to_string(),
+            }],
+            1000,
+            Decimal::one(),
+        );
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::CreateRule { rule }).unwrap_err();
+        assert_eq!(err, ContractError::InvalidConfiguration {});
+
+        // 奖励级条件不会被评估，直接拒绝
+        let mut rule = mock_rule(vec![], 1000, Decimal::one());
+        rule.rewards[0].conditions = vec![RewardCondition {
+            condition_type: "min_level".to_string(),
+            value: "Gold".to_string(),
+        }];
+        let err = execute(deps.as_mut(), env, info, ExecuteMsg::CreateRule { rule }).unwrap_err();
+        assert_eq!(err, ContractError::InvalidConfiguration {});
+    }
+
+    #[test]
+    fn test_capped_upline_does_not_block_downline() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = message_info(&Addr::unchecked("creator"), &[]);
+
+        let mut config = mock_config();
+        config.max_rewards_per_user = 1;
+        instantiate(deps.as_mut(), env.clone(), info.clone(), InstantiateMsg { admin: None, config }).unwrap();
+        fund_treasury(deps.as_mut(), 10_000);
+
+        // a <- b, a <- c
+        let users: Vec<Addr> = ["a", "b", "c"].iter().map(|name| deps.api.addr_make(name)).collect();
+        for user in &users[1..] {
+            execute(deps.as_mut(), env.clone(), message_info(user, &[]), ExecuteMsg::BindReferrer {
+                referrer: users[0].to_string(),
+            }).unwrap();
+        }
+
+        // a 在第一笔推荐奖励后达到上限，c 的奖励照常分发且不再给 a 推荐奖励
+        for user in &users[1..] {
+            execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::DistributeReward {
+                user: user.to_string(),
+                amount: Uint128::from(1000u128),
+                activity_type: ActivityType::Custom { activity_id: "test".to_string() },
+                event_id: None,
+            }).unwrap();
+        }
+
+        for (user, count) in [(&users[0], 1), (&users[1], 1), (&users[2], 1)] {
+            let res = query(deps.as_ref(), env.clone(), QueryMsg::UserRewards { user: user.to_string() }).unwrap();
+            let rewards: Vec<UserReward> = from_json(&res).unwrap();
+            assert_eq!(rewards.len(), count);
+        }
+    }
+
+    #[test]
+    fn test_referral_rewards_follow_upline() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let creator = Addr::unchecked("creator");
+        let info = message_info(&creator, &[]);
+
+        let msg = InstantiateMsg { admin: None, config: mock_config() };
+        instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        fund_treasury(deps.as_mut(), 10_000);
+
+        // a <- b <- c <- d
+        let users: Vec<Addr> = ["a", "b", "c", "d"].iter().map(|name| deps.api.addr_make(name)).collect();
+        for pair in users.windows(2) {
+            execute(deps.as_mut(), env.clone(), message_info(&pair[1], &[]), ExecuteMsg::BindReferrer {
+                referrer: pair[0].to_string(),
+            }).unwrap();
+        }
+
+        // 只能绑定一次，且不能成环
+        let err = execute(deps.as_mut(), env.clone(), message_info(&users[3], &[]), ExecuteMsg::BindReferrer {
+            referrer: users[0].to_string(),
+        }).unwrap_err();
+        assert_eq!(err, ContractError::ReferrerAlreadyBound {});
+        let err = execute(deps.as_mut(), env.clone(), message_info(&users[0], &[]), ExecuteMsg::BindReferrer {
+            referrer: users[3].to_string(),
+        }).unwrap_err();
+        assert_eq!(err, ContractError::InvalidReferrer {});
+
+        execute(deps.as_mut(), env.clone(), info, ExecuteMsg::DistributeReward {
+            user: users[3].to_string(),
+            amount: Uint128::from(1000u128),
+            activity_type: ActivityType::Custom { activity_id: "test".to_string() },
+            event_id: None,
+        }).unwrap();
+
+        for (user, expected) in [(&users[2], 500u128), (&users[1], 200), (&users[0], 100)] {
+            let res = query(deps.as_ref(), env.clone(), QueryMsg::UserRewards { user: user.to_string() }).unwrap();
+            let rewards: Vec<UserReward> = from_json(&res).unwrap();
+            assert_eq!(rewards[0].amount, Uint128::from(expected));
+            assert_eq!(rewards[0].activity_type, ActivityType::Referral { referrer: users[3].to_string() });
+        }
+
+        let res = query(deps.as_ref(), env, QueryMsg::ReferralTree {
+            user: users[1].to_string(),
+            depth: Some(2),
+        }).unwrap();
+        let tree: ReferralTreeResponse = from_json(&res).unwrap();
+        assert_eq!(tree.upline, vec![ReferralNode { user: users[0].to_string(), depth: 1 }]);
+        assert_eq!(tree.downline, vec![
+            ReferralNode { user: users[2].to_string(), depth: 1 },
+            ReferralNode { user: users[3].to_string(), depth: 2 },
+        ]);
+    }
+
+    #[test]
+    fn test_registered_contract_capabilities_and_status() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let creator = Addr::unchecked("creator");
+        let info = message_info(&creator, &[]);
+
+        let msg = InstantiateMsg { admin: None, config: mock_config() };
+        instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        fund_treasury(deps.as_mut(), 10_000);
+
+        let blind_box = deps.api.addr_make("blind_box");
+        let nft = deps.api.addr_make("nft");
+        let voting = deps.api.addr_make("voting");
+        execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::RegisterContract {
+            contract_type: ContractType::BlindBox,
+            contract_addr: blind_box.to_string(),
+            capabilities: vec!["distribute_reward".to_string()],
+        }).unwrap();
+        execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::RegisterContract {
+            contract_type: ContractType::Nft,
+            contract_addr: nft.to_string(),
+            capabilities: vec!["query_points".to_string()],
+        }).unwrap();
+
+        // 省略 capabilities 时视为不授予任何权限
+        let msg: ExecuteMsg = from_json(format!(
+            r#"{{"register_contract":{{"contract_type":"ft","contract_addr":"{}"}}}}"#,
+            deps.api.addr_make("ft")
+        )).unwrap();
+        let ExecuteMsg::RegisterContract { capabilities, .. } = msg else { panic!("unexpected message") };
+        assert!(capabilities.is_empty());
+
+        // 未知权限被拒绝
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::RegisterContract {
+            contract_type: ContractType::Custom("voting".to_string()),
+            contract_addr: voting.to_string(),
+            capabilities: vec!["drain_treasury".to_string()],
+        }).unwrap_err();
+        assert_eq!(err, ContractError::InvalidConfiguration {});
+
+        let distribute = ExecuteMsg::DistributeReward {
+            user: USER.to_string(),
+            amount: Uint128::from(100u128),
+            activity_type: ActivityType::BlindBoxOpen { nft_kind: "rare".to_string(), box_id: "box1".to_string() },
+            event_id: None,
+        };
+        execute(deps.as_mut(), env.clone(), message_info(&blind_box, &[]), distribute.clone()).unwrap();
+
+        // 缺少权限
+        let err = execute(deps.as_mut(), env.clone(), message_info(&nft, &[]), distribute.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        // 暂停后立即失去权限
+        execute(deps.as_mut(), env.clone(), info, ExecuteMsg::UpdateContract {
+            contract_type: ContractType::BlindBox,
+            status: Some(ContractStatus::Suspended),
+            capabilities: None,
+        }).unwrap();
+        let err = execute(deps.as_mut(), env, message_info(&blind_box, &[]), distribute).unwrap_err();
+        assert_eq!(err, ContractError::ContractNotActive {});
+    }
+
+    #[test]
+    fn test_query_contracts_with_pagination_and_filter() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let creator = Addr::unchecked("creator");
+        let info = message_info(&creator, &[]);
+
+        let msg = InstantiateMsg { admin: None, config: mock_config() };
+        instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let contract_types = vec![
+            ContractType::Ft,
+            ContractType::Nft,
+            ContractType::Custom("voting".to_string()),
+            ContractType::Custom("registry".to_string()),
+        ];
+        for contract_type in &contract_types {
+            let addr = deps.api.addr_make(&format!("{:?}", contract_type));
+            execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::RegisterContract {
+                contract_type: contract_type.clone(),
+                contract_addr: addr.to_string(),
+                capabilities: vec![],
+            }).unwrap();
+        }
+
+        let query_contracts = |deps: Deps, kind, start_after, limit| -> Vec<ContractType> {
+            let res = query(deps, mock_env(), QueryMsg::Contracts { kind, start_after, limit }).unwrap();
+            let contracts: Vec<ContractInfo> = from_json(&res).unwrap();
+            contracts.into_iter().map(|c| c.contract_type).collect()
+        };
+
+        // 全部合约分页
+        let all = query_contracts(deps.as_ref(), None, None, None);
+        assert_eq!(all.len(), 4);
+        let first_page = query_contracts(deps.as_ref(), None, None, Some(2));
+        let second_page = query_contracts(deps.as_ref(), None, first_page.last().cloned(), Some(2));
+        assert_eq!([first_page, second_page].concat(), all);
+
+        // 按类型过滤，Custom 按名称排序
+        let custom = query_contracts(deps.as_ref(), Some(ContractKind::Custom), None, None);
+        assert_eq!(custom, vec![
+            ContractType::Custom("registry".to_string()),
+            ContractType::Custom("voting".to_string()),
+        ]);
+        let custom = query_contracts(deps.as_ref(), Some(ContractKind::Custom), Some(custom[0].clone()), None);
+        assert_eq!(custom, vec![ContractType::Custom("voting".to_string())]);
+        assert_eq!(query_contracts(deps.as_ref(), Some(ContractKind::Nft), None, None), vec![ContractType::Nft]);
+        assert!(query_contracts(deps.as_ref(), Some(ContractKind::BlindBox), None, None).is_empty());
+
+        // 单个查询与地址反查
+        let voting = ContractType::Custom("voting".to_string());
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::Contract { contract_type: voting.clone() }).unwrap();
+        let info: ContractInfo = from_json(&res).unwrap();
+        let res = query(deps.as_ref(), env, QueryMsg::ContractByAddress {
+            addr: info.contract_addr.to_string(),
+        }).unwrap();
+        let by_addr: ContractInfo = from_json(&res).unwrap();
+        assert_eq!(by_addr.contract_type, voting);
+    }
+
+    #[test]
+    fn test_system_stats_track_state_changes() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let creator = Addr::unchecked("creator");
+        let info = message_info(&creator, &[]);
+
+        let msg = InstantiateMsg { admin: None, config: mock_config() };
+        instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        fund_treasury(deps.as_mut(), 10_000);
+
+        let ft_addr = deps.api.addr_make("ft");
+        execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::RegisterContract {
+            contract_type: ContractType::Ft,
+            contract_addr: ft_addr.to_string(),
+            capabilities: vec![],
+        }).unwrap();
+        execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::CreateRule {
+            rule: mock_rule(vec![], 100, Decimal::one()),
+        }).unwrap();
+        execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::CreateRule {
+            rule: mock_rule(vec![], 100, Decimal::one()),
+        }).unwrap();
+        execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::DeleteRule {
+            rule_id: "rule_0".to_string(),
+        }).unwrap();
+
+        let blind_box = ActivityType::BlindBoxOpen { nft_kind: "rare".to_string(), box_id: "box1".to_string() };
+        for (user, amount) in [(USER, 300u128), (USER, 200), ("luckee1other", 400)] {
+            execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::DistributeReward {
+                user: user.to_string(),
+                amount: Uint128::from(amount),
+                activity_type: blind_box.clone(),
+                event_id: None,
+            }).unwrap();
+        }
+        execute(deps.as_mut(), env.clone(), message_info(&Addr::unchecked(USER), &[]), ExecuteMsg::ClaimReward {
+            reward_id: "reward_0".to_string(),
+        }).unwrap();
+
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::SystemStats {}).unwrap();
+        let stats: SystemStats = from_json(&res).unwrap();
+        assert_eq!(stats.total_users, 2);
+        assert_eq!(stats.total_rules, 1);
+        assert_eq!(stats.total_contracts, 1);
+        assert_eq!(stats.total_rewards_distributed, Uint128::from(900u128));
+        assert_eq!(stats.status_totals.pending, RewardTotals { count: 2, amount: Uint128::from(600u128) });
+        assert_eq!(stats.status_totals.claimed, RewardTotals { count: 1, amount: Uint128::from(300u128) });
+        assert_eq!(stats.last_updated, env.block.time);
+
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::ActivityStats { start_after: None, limit: None }).unwrap();
+        let activity: ActivityStatsResponse = from_json(&res).unwrap();
+        assert_eq!(activity.activities, vec![ActivityStats {
+            activity: "blind_box_open".to_string(),
+            totals: RewardTotals { count: 3, amount: Uint128::from(900u128) },
+        }]);
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::ActivityStats {
+            start_after: Some("blind_box_open".to_string()),
+            limit: None,
+        }).unwrap();
+        let activity: ActivityStatsResponse = from_json(&res).unwrap();
+        assert!(activity.activities.is_empty());
+    }
+
+    #[test]
+    fn test_expired_rewards_are_swept_back_to_treasury() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let creator = Addr::unchecked("creator");
+        let info = message_info(&creator, &[]);
+
+        let msg = InstantiateMsg { admin: None, config: mock_config() };
+        instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        fund_treasury(deps.as_mut(), 1000);
+
+        for amount in [300u128, 200] {
+            execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::DistributeReward {
+                user: USER.to_string(),
+                amount: Uint128::from(amount),
+                activity_type: ActivityType::Custom { activity_id: "test".to_string() },
+                event_id: None,
+            }).unwrap();
+        }
+        let created_at = env.block.time;
+
+        // 30 天后过期，超时领取被拒绝
+        env.block.time = created_at.plus_days(30);
+        let user_info = message_info(&Addr::unchecked(USER), &[]);
+        let err = execute(deps.as_mut(), env.clone(), user_info, ExecuteMsg::ClaimReward {
+            reward_id: "reward_0".to_string(),
+        }).unwrap_err();
+        assert_eq!(err, ContractError::RewardExpired {});
+
+        // 任何人都可以分批清理
+        let anyone = message_info(&Addr::unchecked("anyone"), &[]);
+        let res = execute(deps.as_mut(), env.clone(), anyone.clone(), ExecuteMsg::ExpireRewards { limit: Some(1) }).unwrap();
+        assert!(res.attributes.iter().any(|a| a.key == "reward_ids" && a.value == "reward_0"));
+        let res = execute(deps.as_mut(), env.clone(), anyone.clone(), ExecuteMsg::ExpireRewards { limit: None }).unwrap();
+        assert!(res.attributes.iter().any(|a| a.key == "reward_ids" && a.value == "reward_1"));
+        let res = execute(deps.as_mut(), env.clone(), anyone, ExecuteMsg::ExpireRewards { limit: None }).unwrap();
+        assert!(res.attributes.iter().any(|a| a.key == "expired_count" && a.value == "0"));
+
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::UserRewards { user: USER.to_string() }).unwrap();
+        let rewards: Vec<UserReward> = from_json(&res).unwrap();
+        assert!(rewards.iter().all(|r| r.status == RewardStatus::Expired));
+        assert_eq!(rewards[0].expires_at, Some(created_at.plus_days(30)));
+
+        // 负债退回可用余额
+        let balances = query_treasury(deps.as_ref());
+        assert_eq!(balances[0].available, Uint128::from(1000u128));
+        assert_eq!(balances[0].committed, Uint128::zero());
+
+        let res = query(deps.as_ref(), env, QueryMsg::SystemStats {}).unwrap();
+        let stats: SystemStats = from_json(&res).unwrap();
+        assert_eq!(stats.status_totals.expired, RewardTotals { count: 2, amount: Uint128::from(500u128) });
+    }
+
+    #[test]
+    fn test_points_delta_drives_level_progression() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let creator = Addr::unchecked("creator");
+        let info = message_info(&creator, &[]);
+
+        let mut config = mock_config();
+        config.level_up_reward = Uint128::from(100u128);
+        let msg = InstantiateMsg { admin: None, config };
+        instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        fund_treasury(deps.as_mut(), 1000);
+
+        let query_level = |deps: Deps| -> UserLevelInfo {
+            let res = query(deps, mock_env(), QueryMsg::UserLevel { user: USER.to_string() }).unwrap();
+            from_json(&res).unwrap()
+        };
+
+        execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::AddPoints {
+            user: USER.to_string(),
+            points: 600,
+        }).unwrap();
+        assert_eq!(query_level(deps.as_ref()).level, UserLevel::Bronze);
+
+        // 两次增量累加后跨越两级
+        execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::AddPoints {
+            user: USER.to_string(),
+            points: 4400,
+        }).unwrap();
+        let level = query_level(deps.as_ref());
+        assert_eq!(level.points, 5000);
+        assert_eq!(level.level, UserLevel::Gold);
+        assert_eq!(level.level_up_count, 2);
+        assert_eq!(level.last_level_up, Some(env.block.time));
+
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::UserRewards { user: USER.to_string() }).unwrap();
+        let rewards: Vec<UserReward> = from_json(&res).unwrap();
+        assert_eq!(rewards[0].activity_type, ActivityType::LevelUp { new_level: UserLevel::Gold });
+        assert_eq!(rewards[0].amount, Uint128::from(100u128));
+
+        // 未开启降级时积分减少不影响等级
+        execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::SubtractPoints {
+            user: USER.to_string(),
+            points: 4500,
+        }).unwrap();
+        assert_eq!(query_level(deps.as_ref()).level, UserLevel::Gold);
+
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::SubtractPoints {
+            user: USER.to_string(),
+            points: 501,
+        }).unwrap_err();
+        assert_eq!(err, ContractError::InsufficientPoints {});
+
+        // 开启降级
+        let mut config = mock_config();
+        config.level_demotion_enabled = true;
+        execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::UpdateConfig { config }).unwrap();
+        execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::SubtractPoints {
+            user: USER.to_string(),
+            points: 1,
+        }).unwrap();
+        assert_eq!(query_level(deps.as_ref()).level, UserLevel::Bronze);
+
+        // 金库可用余额不足时跳过升级奖励，积分与等级照常保存
+        let mut config = mock_config();
+        config.level_demotion_enabled = true;
+        config.level_up_reward = Uint128::from(1000u128);
+        execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::UpdateConfig { config: config.clone() }).unwrap();
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::AddPoints {
+            user: USER.to_string(),
+            points: 501,
+        }).unwrap();
+        assert!(res.attributes.iter().any(|a| a.key == "level_up_reward_skipped" && a.value == "insufficient_treasury"));
+        let level = query_level(deps.as_ref());
+        assert_eq!(level.points, 1000);
+        assert_eq!(level.level, UserLevel::Silver);
+
+        // 待领取奖励达到上限时同样跳过
+        config.level_up_reward = Uint128::from(100u128);
+        config.max_rewards_per_user = 1;
+        execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::UpdateConfig { config }).unwrap();
+        let res = execute(deps.as_mut(), env.clone(), info, ExecuteMsg::AddPoints {
+            user: USER.to_string(),
+            points: 4000,
+        }).unwrap();
+        assert!(res.attributes.iter().any(|a| a.key == "level_up_reward_skipped" && a.value == "reward_limit"));
+        assert_eq!(query_level(deps.as_ref()).level, UserLevel::Gold);
+
+        let res = query(deps.as_ref(), env, QueryMsg::UserRewards { user: USER.to_string() }).unwrap();
+        let rewards: Vec<UserReward> = from_json(&res).unwrap();
+        assert_eq!(rewards.len(), 1);
+        let balances = query_treasury(deps.as_ref());
+        assert_eq!(balances[0].committed, Uint128::from(100u128));
+    }
+
+    #[test]
+    fn test_level_multiplier_applied_to_distribution() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let creator = Addr::unchecked("creator");
+        let info = message_info(&creator, &[]);
+
+        let msg = InstantiateMsg { admin: None, config: mock_config() };
+        instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        fund_treasury(deps.as_mut(), 10_000);
+
+        // 升到 Gold，倍数 1.5
+        execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::AddPoints {
+            user: USER.to_string(),
+            points: 5000,
+        }).unwrap();
+        let res = execute(deps.as_mut(), env.clone(), info, ExecuteMsg::DistributeReward {
+            user: USER.to_string(),
+            amount: Uint128::from(1000u128),
+            activity_type: ActivityType::Custom { activity_id: "test".to_string() },
+            event_id: None,
+        }).unwrap();
+        assert!(res.attributes.iter().any(|a| a.key == "amount" && a.value == "1500"));
+
+        let res = query(deps.as_ref(), env, QueryMsg::UserRewards { user: USER.to_string() }).unwrap();
+        let rewards: Vec<UserReward> = from_json(&res).unwrap();
+        assert_eq!(rewards[0].base_amount, Uint128::from(1000u128));
+        assert_eq!(rewards[0].multiplier, Decimal::percent(150));
+        assert_eq!(rewards[0].amount, Uint128::from(1500u128));
+
+        // 金库按最终金额锁定
+        let balances = query_treasury(deps.as_ref());
+        assert_eq!(balances[0].committed, Uint128::from(1500u128));
+    }
+
+    #[test]
+    fn test_max_pending_rewards_per_user() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let creator = Addr::unchecked("creator");
+        let info = message_info(&creator, &[]);
+
+        let mut config = mock_config();
+        config.max_rewards_per_user = 2;
+        instantiate(deps.as_mut(), env.clone(), info.clone(), InstantiateMsg { admin: None, config }).unwrap();
+        fund_treasury(deps.as_mut(), 10_000);
+
+        let distribute = ExecuteMsg::DistributeReward {
+            user: USER.to_string(),
+            amount: Uint128::from(100u128),
+            activity_type: ActivityType::Custom { activity_id: "test".to_string() },
+            event_id: None,
+        };
+        execute(deps.as_mut(), env.clone(), info.clone(), distribute.clone()).unwrap();
+        execute(deps.as_mut(), env.clone(), info.clone(), distribute.clone()).unwrap();
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), distribute.clone()).unwrap_err();
+        assert_eq!(err, ContractError::RewardLimitExceeded {});
+
+        // 领取后释放名额
+        let user = Addr::unchecked(USER);
+        execute(deps.as_mut(), env.clone(), message_info(&user, &[]), ExecuteMsg::ClaimReward {
+            reward_id: "reward_1".to_string(),
+        }).unwrap();
+        execute(deps.as_mut(), env.clone(), info, distribute).unwrap();
+
+        let res = query(deps.as_ref(), env, QueryMsg::UserRewards { user: USER.to_string() }).unwrap();
+        let rewards: Vec<UserReward> = from_json(&res).unwrap();
+        assert_eq!(rewards.len(), 3);
+    }
+
+    #[test]
+    fn test_migrate_populated_legacy_store() {
+        use cosmwasm_schema::cw_serde;
+        use cw_storage_plus::{Item, Map};
+
+        // 0.1.0 的配置与奖励布局，缺少后续新增的字段
+        #[cw_serde]
+        struct LegacyConfig {
+            max_rewards_per_user: u32,
+            reward_expiration_days: u32,
+            auto_claim_enabled: bool,
+        }
+        #[cw_serde]
+        struct LegacyReward {
+            reward_id: String,
+            user: String,
+            amount: Uint128,
+            reward_type: RewardType,
+            activity_type: ActivityType,
+            created_at: Timestamp,
+            claimed_at: Option<Timestamp>,
+            expires_at: Option<Timestamp>,
+            status: RewardStatus,
+        }
+        const LEGACY_CONFIG: Item<LegacyConfig> = Item::new("config");
+        const LEGACY_REWARDS: Map<String, Vec<LegacyReward>> = Map::new("user_rewards");
+
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let creator = Addr::unchecked("creator");
+        cw2::set_contract_version(deps.as_mut().storage, "luckee-incentive", "0.1.0").unwrap();
+        LEGACY_CONFIG.save(deps.as_mut().storage, &LegacyConfig {
+            max_rewards_per_user: 1000,
+            reward_expiration_days: 30,
+            auto_claim_enabled: true,
+        }).unwrap();
+
+        let legacy = |id: &str, user: &str, status: RewardStatus| LegacyReward {
+            reward_id: id.to_string(),
+            user: user.to_string(),
+            amount: Uint128::from(100u128),
+            reward_type: RewardType::LevelPoints,
+            activity_type: ActivityType::Custom { activity_id: "test".to_string() },
+            created_at: env.block.time,
+            claimed_at: None,
+            expires_at: Some(env.block.time.plus_days(1)),
+            status,
+        };
+        LEGACY_REWARDS.save(deps.as_mut().storage, USER.to_string(), &vec![
+            legacy("reward_0", USER, RewardStatus::Claimed),
+            legacy("reward_2", USER, RewardStatus::Pending),
+        ]).unwrap();
+        // 待领取的代币奖励没有资产记录，也没有金库记账
+        LEGACY_REWARDS.save(deps.as_mut().storage, "other".to_string(), &vec![
+            legacy("reward_1", "other", RewardStatus::Pending),
+            LegacyReward { reward_type: RewardType::Token, ..legacy("reward_3", "other", RewardStatus::Pending) },
+        ]).unwrap();
+
+        // 0.1.0 已有的规则、合约与等级记录，但没有地址索引与统计
+        let ft = Addr::unchecked("ft_contract");
+        crate::state::RULES.save(deps.as_mut().storage, "rule_0".to_string(), &RuleDetails {
+            rule_id: "rule_0".to_string(),
+            ..mock_rule(vec![], 100, Decimal::one())
+        }).unwrap();
+        crate::state::CONTRACTS.save(deps.as_mut().storage, ContractType::Ft, &ContractInfo {
+            contract_type: ContractType::Ft,
+            contract_addr: ft.clone(),
+            status: ContractStatus::Active,
+            capabilities: vec![],
+            registered_at: env.block.time,
+        }).unwrap();
+        for user in [USER, "levels_only"] {
+            crate::state::USER_LEVELS.save(deps.as_mut().storage, user.to_string(), &UserLevelInfo {
+                user: user.to_string(),
+                level: UserLevel::Bronze,
+                points: 10,
+                level_up_count: 0,
+                last_level_up: None,
+                total_rewards: Uint128::zero(),
+            }).unwrap();
+        }
+
+        // 旧配置无法解析为新结构，必须随迁移提供
+        let err = migrate(deps.as_mut(), env.clone(), MigrateMsg { config: None }).unwrap_err();
+        assert_eq!(err, ContractError::InvalidConfiguration {});
+
+        let res = migrate(deps.as_mut(), env.clone(), MigrateMsg { config: Some(mock_config()) }).unwrap();
+        assert!(res.attributes.iter().any(|a| a.key == "step" && a.value == "index_user_rewards"));
+        assert!(res.attributes.iter().any(|a| a.key == "migrated_rewards" && a.value == "4"));
+        assert!(res.attributes.iter().any(|a| a.key == "indexed_contracts" && a.value == "1"));
+        assert!(res.attributes.iter().any(|a| a.key == "step" && a.value == "backfill_stats"));
+        assert!(LEGACY_REWARDS.is_empty(deps.as_ref().storage));
+        let version = cw2::get_contract_version(deps.as_ref().storage).unwrap();
+        assert_eq!(version.version, env!("CARGO_PKG_VERSION"));
+
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::UserRewards { user: USER.to_string() }).unwrap();
+        let rewards: Vec<UserReward> = from_json(&res).unwrap();
+        assert_eq!(rewards.len(), 2);
+        assert_eq!(rewards[1].base_amount, Uint128::from(100u128));
+        assert_eq!(rewards[1].multiplier, Decimal::one());
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::Reward { reward_id: "reward_1".to_string() }).unwrap();
+        let res: RewardResponse = from_json(&res).unwrap();
+        assert_eq!(res.owner, "other");
+
+        // 代币奖励按新配置的发放方式补上资产，待领取金额记入金库锁定余额
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::Reward { reward_id: "reward_3".to_string() }).unwrap();
+        let res: RewardResponse = from_json(&res).unwrap();
+        let native = AssetInfo::Native { denom: "uluckee".to_string() };
+        assert_eq!(res.reward.asset, Some(native.clone()));
+        let balances = query_treasury(deps.as_ref());
+        assert_eq!(balances[0].asset, native);
+        assert_eq!(balances[0].available, Uint128::zero());
+        assert_eq!(balances[0].committed, Uint128::from(100u128));
+
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::SystemStats {}).unwrap();
+        let stats: SystemStats = from_json(&res).unwrap();
+        assert_eq!(stats.status_totals.pending.count, 3);
+        assert_eq!(stats.status_totals.claimed.count, 1);
+        assert_eq!(stats.total_rules, 1);
+        assert_eq!(stats.total_contracts, 1);
+        // USER 与 other 来自奖励，levels_only 只有等级记录
+        assert_eq!(stats.total_users, 3);
+
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::ContractByAddress { addr: ft.to_string() }).unwrap();
+        let contract: ContractInfo = from_json(&res).unwrap();
+        assert_eq!(contract.contract_type, ContractType::Ft);
+
+        // 迁移后的待领取奖励仍可被过期清理
+        let mut later = env.clone();
+        later.block.time = env.block.time.plus_days(2);
+        let res = execute(deps.as_mut(), later, message_info(&creator, &[]), ExecuteMsg::ExpireRewards {
+            limit: None,
+        }).unwrap();
+        assert!(res.attributes.iter().any(|a| a.key == "expired_count" && a.value == "3"));
+
+        // 同版本重复迁移不会再次执行步骤
+        let res = migrate(deps.as_mut(), env, MigrateMsg { config: None }).unwrap();
+        assert!(!res.attributes.iter().any(|a| a.key == "step"));
+    }
+
+    #[test]
+    fn test_migrate_rejects_foreign_contract_and_downgrade() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+
+        cw2::set_contract_version(deps.as_mut().storage, "cw20-base", "0.1.0").unwrap();
+        let err = migrate(deps.as_mut(), env.clone(), MigrateMsg { config: Some(mock_config()) }).unwrap_err();
+        assert_eq!(err, ContractError::WrongContract { contract: "cw20-base".to_string() });
+
+        cw2::set_contract_version(deps.as_mut().storage, "luckee-incentive", "99.0.0").unwrap();
+        let err = migrate(deps.as_mut(), env.clone(), MigrateMsg { config: Some(mock_config()) }).unwrap_err();
+        assert_eq!(err, ContractError::CannotDowngrade {
+            from: "99.0.0".to_string(),
+            to: env!("CARGO_PKG_VERSION").to_string(),
+        });
+
+        cw2::set_contract_version(deps.as_mut().storage, "luckee-incentive", "latest").unwrap();
+        let err = migrate(deps.as_mut(), env, MigrateMsg { config: Some(mock_config()) }).unwrap_err();
+        assert_eq!(err, ContractError::InvalidVersion { version: "latest".to_string() });
+    }
+
+    #[test]
+    fn test_reward_history_filters_and_paginates() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let creator = Addr::unchecked("creator");
+        let info = message_info(&creator, &[]);
+
+        instantiate(deps.as_mut(), env.clone(), info.clone(), InstantiateMsg { admin: None, config: mock_config() })
+            .unwrap();
+        fund_treasury(deps.as_mut(), 10_000);
+
+        // 3 个盲盒奖励 + 2 个自定义活动奖励
+        for i in 0..5u128 {
+            let activity_type = if i < 3 {
+                ActivityType::BlindBoxOpen { nft_kind: "rare".to_string(), box_id: format!("box{}", i) }
+            } else {
+                ActivityType::Custom { activity_id: "quest".to_string() }
+            };
+            execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::DistributeReward {
+                user: USER.to_string(),
+                amount: Uint128::from(100 * (i + 1)),
+                activity_type,
+                event_id: None,
+            }).unwrap();
+        }
+        let user = Addr::unchecked(USER);
+        execute(deps.as_mut(), env.clone(), message_info(&user, &[]), ExecuteMsg::ClaimReward {
+            reward_id: "reward_2".to_string(),
+        }).unwrap();
+
+        let history = |deps: Deps, status, activity_type, start_after, limit| -> RewardHistoryResponse {
+            let msg = QueryMsg::RewardHistory {
+                user: USER.to_string(),
+                status,
+                activity_type,
+                from_time: None,
+                to_time: None,
+                start_after,
+                limit,
+            };
+            from_json(query(deps, mock_env(), msg).unwrap()).unwrap()
+        };
+
+        let ids = |res: RewardHistoryResponse| -> Vec<String> {
+            res.rewards.into_iter().map(|reward| reward.reward_id).collect()
+        };
+
+        // 分页，合计覆盖全部记录
+        let res = history(deps.as_ref(), None, None, None, Some(2));
+        assert_eq!(res.total_count, 5);
+        assert_eq!(res.total_rewards, Uint128::from(1500u128));
+        assert_eq!(ids(res), vec!["reward_0", "reward_1"]);
+        let res = history(deps.as_ref(), None, None, Some("reward_2".to_string()), Some(2));
+        assert_eq!(ids(res), vec!["reward_3", "reward_4"]);
+
+        // 按活动与状态过滤
+        let res = history(deps.as_ref(), None, Some("blind_box_open".to_string()), None, None);
+        assert_eq!(ids(res), vec!["reward_0", "reward_1", "reward_2"]);
+        let res = history(deps.as_ref(), Some(RewardStatus::Pending), Some("blind_box_open".to_string()), None, None);
+        assert_eq!(ids(res), vec!["reward_0", "reward_1"]);
+        // 领取后 reward_2 从待领取合计转入已领取合计
+        let res = history(deps.as_ref(), Some(RewardStatus::Pending), None, None, Some(1));
+        assert_eq!((res.total_count, res.total_rewards), (4, Uint128::from(1200u128)));
+        let res = history(deps.as_ref(), Some(RewardStatus::Claimed), None, None, None);
+        assert_eq!((res.total_count, res.total_rewards), (1, Uint128::from(300u128)));
+        let res = history(deps.as_ref(), Some(RewardStatus::Pending), None, Some("reward_1".to_string()), Some(1));
+        assert_eq!(ids(res), vec!["reward_3"]);
+
+        // 时间窗口之外没有记录
+        let msg = QueryMsg::RewardHistory {
+            user: USER.to_string(),
+            status: None,
+            activity_type: None,
+            from_time: Some(env.block.time.plus_seconds(1)),
+            to_time: None,
+            start_after: None,
+            limit: None,
+        };
+        let res: RewardHistoryResponse = from_json(query(deps.as_ref(), env, msg).unwrap()).unwrap();
+        assert!(res.rewards.is_empty());
+    }
+
+    #[test]
+    fn test_reward_lookup_and_cancel() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let creator = Addr::unchecked("creator");
+        let info = message_info(&creator, &[]);
+
+        instantiate(deps.as_mut(), env.clone(), info.clone(), InstantiateMsg { admin: None, config: mock_config() })
+            .unwrap();
+        fund_treasury(deps.as_mut(), 1000);
+        execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::DistributeReward {
+            user: USER.to_string(),
+            amount: Uint128::from(400u128),
+            activity_type: ActivityType::Custom { activity_id: "test".to_string() },
+            event_id: None,
+        }).unwrap();
+
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::Reward { reward_id: "reward_0".to_string() }).unwrap();
+        let res: RewardResponse = from_json(&res).unwrap();
+        assert_eq!(res.owner, USER);
+        assert_eq!(res.reward.amount, Uint128::from(400u128));
+        assert!(query(deps.as_ref(), env.clone(), QueryMsg::Reward { reward_id: "reward_9".to_string() }).is_err());
+
+        // 只有管理员可以作废奖励
+        let user = Addr::unchecked(USER);
+        let err = execute(deps.as_mut(), env.clone(), message_info(&user, &[]), ExecuteMsg::CancelReward {
+            reward_id: "reward_0".to_string(),
+        }).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        execute(deps.as_mut(), env.clone(), info, ExecuteMsg::CancelReward { reward_id: "reward_0".to_string() })
+            .unwrap();
+        let balances = query_treasury(deps.as_ref());
+        assert_eq!(balances[0].available, Uint128::from(1000u128));
+        assert_eq!(balances[0].committed, Uint128::zero());
+
+        let err = execute(deps.as_mut(), env, message_info(&user, &[]), ExecuteMsg::ClaimReward {
+            reward_id: "reward_0".to_string(),
+        }).unwrap_err();
+        assert_eq!(err, ContractError::OperationNotAllowed {});
+    }
+
+    #[test]
+    fn test_batch_claims_aggregate_payouts() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let creator = Addr::unchecked("creator");
+        let info = message_info(&creator, &[]);
+
+        instantiate(deps.as_mut(), env.clone(), info.clone(), InstantiateMsg { admin: None, config: mock_config() })
+            .unwrap();
+        fund_treasury(deps.as_mut(), 10_000);
+        for amount in [100u128, 200, 300, 400] {
+            execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::DistributeReward {
+                user: USER.to_string(),
+                amount: Uint128::from(amount),
+                activity_type: ActivityType::Custom { activity_id: "test".to_string() },
+                event_id: None,
+            }).unwrap();
+        }
+
+        // 合并为一笔转账，无效ID单独报告
+        let user = Addr::unchecked(USER);
+        let res = execute(deps.as_mut(), env.clone(), message_info(&user, &[]), ExecuteMsg::ClaimRewards {
+            reward_ids: vec!["reward_0".to_string(), "reward_1".to_string(), "reward_9".to_string()],
+        }).unwrap();
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.messages[0].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: USER.to_string(),
+            amount: coins(300, "uluckee"),
+        }));
+        let data: ClaimRewardsResponse = from_json(res.data.unwrap()).unwrap();
+        assert_eq!(data.claimed_count, 2);
+        assert!(!data.outcomes[2].claimed);
+        assert_eq!(data.outcomes[2].error, Some(ContractError::RewardNotFound {}.to_string()));
+        assert!(res.attributes.contains(&attr("claimed_ids", "reward_0,reward_1")));
+        assert!(res.attributes.contains(&attr("failed_ids", "reward_9")));
+
+        // 调用者提供的ID不会成为属性键，空ID或以 `_` 开头的ID不影响交易
+        let res = execute(deps.as_mut(), env.clone(), message_info(&user, &[]), ExecuteMsg::ClaimRewards {
+            reward_ids: vec![String::new(), "_contract_address".to_string()],
+        }).unwrap();
+        assert!(res.attributes.iter().all(|a| !a.key.is_empty() && !a.key.starts_with('_')));
+        assert!(res.attributes.contains(&attr("failed_ids", ",_contract_address")));
+
+        // ClaimAll 领取剩余的待领取奖励
+        let res = execute(deps.as_mut(), env.clone(), message_info(&user, &[]), ExecuteMsg::ClaimAll { limit: None })
+            .unwrap();
+        assert_eq!(res.messages[0].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: USER.to_string(),
+            amount: coins(700, "uluckee"),
+        }));
+        let data: ClaimRewardsResponse = from_json(res.data.unwrap()).unwrap();
+        assert_eq!(data.claimed_count, 2);
+
+        let balances = query_treasury(deps.as_ref());
+        assert_eq!(balances[0].committed, Uint128::zero());
+        assert_eq!(balances[0].available, Uint128::from(9000u128));
+
+        let res = execute(deps.as_mut(), env, message_info(&user, &[]), ExecuteMsg::ClaimAll { limit: None }).unwrap();
+        assert!(res.messages.is_empty());
+    }
+
+    #[test]
+    fn test_auto_claim_pays_out_on_distribution() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let creator = Addr::unchecked("creator");
+        let info = message_info(&creator, &[]);
+
+        let mut config = mock_config();
+        config.auto_claim_enabled = true;
+        instantiate(deps.as_mut(), env.clone(), info.clone(), InstantiateMsg { admin: None, config }).unwrap();
+        fund_treasury(deps.as_mut(), 10_000);
+
+        let distribute = ExecuteMsg::DistributeReward {
+            user: USER.to_string(),
+            amount: Uint128::from(500u128),
+            activity_type: ActivityType::Custom { activity_id: "test".to_string() },
+            event_id: None,
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), distribute.clone()).unwrap();
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.messages[0].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: USER.to_string(),
+            amount: coins(500, "uluckee"),
+        }));
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::Reward { reward_id: "reward_0".to_string() }).unwrap();
+        let res: RewardResponse = from_json(&res).unwrap();
+        assert_eq!(res.reward.status, RewardStatus::Claimed);
+        assert_eq!(query_treasury(deps.as_ref())[0].committed, Uint128::zero());
+
+        // NFT 奖励仍需手动领取
+        let collection = Addr::unchecked("collection");
+        execute(deps.as_mut(), env.clone(), message_info(&collection, &[]), ExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+            sender: creator.to_string(),
+            token_id: "1".to_string(),
+            msg: to_json_binary(&ReceiveNftMsg::DepositEscrow {}).unwrap(),
+        })).unwrap();
+        let rule = RuleDetails {
+            rewards: vec![RewardDefinition {
+                reward_type: RewardType::Nft,
+                amount: Uint128::one(),
+                multiplier: Decimal::one(),
+                conditions: vec![],
+                nft: Some(NftRewardSource::Escrow { collection: collection.to_string(), token_id: None }),
+                vesting: None,
+            }],
+            ..mock_rule(vec![], 0, Decimal::one())
+        };
+        execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::CreateRule { rule }).unwrap();
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::ReportActivity {
+            user: USER.to_string(),
+            activity_type: ActivityType::Custom { activity_id: "test".to_string() },
+            value: Uint128::zero(),
+            event_id: None,
+        }).unwrap();
+        assert!(res.messages.is_empty());
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::Reward { reward_id: "reward_1".to_string() }).unwrap();
+        let res: RewardResponse = from_json(&res).unwrap();
+        assert_eq!(res.reward.status, RewardStatus::Pending);
+
+        // 用户关闭自动领取后回到待领取流程
+        let user = Addr::unchecked(USER);
+        execute(deps.as_mut(), env.clone(), message_info(&user, &[]), ExecuteMsg::SetAutoClaim { enabled: false })
+            .unwrap();
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::AutoClaim { user: USER.to_string() }).unwrap();
+        let res: AutoClaimResponse = from_json(&res).unwrap();
+        assert!(!res.enabled);
+        assert!(res.opted_out);
+
+        let res = execute(deps.as_mut(), env.clone(), info, distribute).unwrap();
+        assert!(res.messages.is_empty());
+        assert_eq!(query_treasury(deps.as_ref())[0].committed, Uint128::from(500u128));
+    }
+
+    #[test]
+    fn test_auto_claim_deferred_while_claims_paused() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = message_info(&Addr::unchecked("creator"), &[]);
+
+        let mut config = mock_config();
+        config.auto_claim_enabled = true;
+        instantiate(deps.as_mut(), env.clone(), info.clone(), InstantiateMsg { admin: None, config }).unwrap();
+        fund_treasury(deps.as_mut(), 10_000);
+
+        let distribute = ExecuteMsg::DistributeReward {
+            user: USER.to_string(),
+            amount: Uint128::from(500u128),
+            activity_type: ActivityType::Custom { activity_id: "test".to_string() },
+            event_id: None,
+        };
+
+        // 暂停领取时分发照常进行，但奖励保持待领取，不转出资产
+        execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::Pause { scope: PauseScope::Claims }).unwrap();
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), distribute.clone()).unwrap();
+        assert!(res.messages.is_empty());
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::Reward { reward_id: "reward_0".to_string() }).unwrap();
+        let res: RewardResponse = from_json(&res).unwrap();
+        assert_eq!(res.reward.status, RewardStatus::Pending);
+        assert_eq!(query_treasury(deps.as_ref())[0].committed, Uint128::from(500u128));
+
+        // 恢复后新奖励重新自动发放
+        execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::Unpause { scope: PauseScope::Claims }).unwrap();
+        let res = execute(deps.as_mut(), env, info, distribute).unwrap();
+        assert_eq!(res.messages[0].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: USER.to_string(),
+            amount: coins(500, "uluckee"),
+        }));
+    }
+
+    #[test]
+    fn test_distribute_rewards_batch() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let creator = Addr::unchecked("creator");
+        let info = message_info(&creator, &[]);
+
+        let mut config = mock_config();
+        config.max_batch_size = 3;
+        instantiate(deps.as_mut(), env.clone(), info.clone(), InstantiateMsg { admin: None, config }).unwrap();
+        fund_treasury(deps.as_mut(), 1000);
+
+        let entry = |user: &str, amount: u128| DistributionEntry {
+            user: user.to_string(),
+            amount: Uint128::from(amount),
+            activity_type: ActivityType::Custom { activity_id: "leaderboard".to_string() },
+        };
+
+        // 重复用户与超出批量上限
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::DistributeRewards {
+            entries: vec![entry("alice", 100), entry("bob", 100), entry("alice", 100)],
+        }).unwrap_err();
+        assert_eq!(err, ContractError::DuplicateUser { user: "alice".to_string() });
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::DistributeRewards {
+            entries: vec![entry("a", 1), entry("b", 1), entry("c", 1), entry("d", 1)],
+        }).unwrap_err();
+        assert_eq!(err, ContractError::BatchTooLarge { max: 3 });
+
+        let res = execute(deps.as_mut(), env.clone(), info, ExecuteMsg::DistributeRewards {
+            entries: vec![entry("alice", 300), entry("bob", 200), entry("carol", 100)],
+        }).unwrap();
+        let summary: DistributeRewardsResponse = from_json(res.data.unwrap()).unwrap();
+        assert_eq!(summary.count, 3);
+        assert_eq!(summary.total_amount, Uint128::from(600u128));
+        assert_eq!(summary.first_reward_id, "reward_0");
+        assert_eq!(summary.last_reward_id, "reward_2");
+        assert_eq!(query_treasury(deps.as_ref())[0].committed, Uint128::from(600u128));
+    }
+
+    #[test]
+    fn test_event_id_makes_distribution_idempotent() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let creator = Addr::unchecked("creator");
+        let info = message_info(&creator, &[]);
+
+        instantiate(deps.as_mut(), env.clone(), info.clone(), InstantiateMsg { admin: None, config: mock_config() })
+            .unwrap();
+        fund_treasury(deps.as_mut(), 1000);
+
+        let distribute = ExecuteMsg::DistributeReward {
+            user: USER.to_string(),
+            amount: Uint128::from(300u128),
+            activity_type: ActivityType::Custom { activity_id: "test".to_string() },
+            event_id: Some("tx-1".to_string()),
+        };
+        execute(deps.as_mut(), env.clone(), info.clone(), distribute.clone()).unwrap();
+
+        // 重试的交易不会重复分发
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), distribute).unwrap();
+        assert!(res.attributes.iter().any(|a| a.key == "duplicate" && a.value == "true"));
+        assert!(res.attributes.iter().any(|a| a.key == "reward_ids" && a.value == "reward_0"));
+        assert_eq!(query_treasury(deps.as_ref())[0].committed, Uint128::from(300u128));
+
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::EventProcessed { event_id: "tx-1".to_string() }).unwrap();
+        let res: EventProcessedResponse = from_json(&res).unwrap();
+        assert!(res.processed);
+        assert_eq!(res.reward_ids, vec!["reward_0".to_string()]);
+        assert_eq!(res.processed_at, Some(env.block.time));
+
+        let res = query(deps.as_ref(), env, QueryMsg::EventProcessed { event_id: "tx-2".to_string() }).unwrap();
+        let res: EventProcessedResponse = from_json(&res).unwrap();
+        assert!(!res.processed);
+        assert!(res.reward_ids.is_empty());
+    }
+
+    #[test]
+    fn test_pause_scopes_block_operations() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let creator = deps.api.addr_make("creator");
+        let pauser = deps.api.addr_make("pauser");
+        let info = message_info(&creator, &[]);
+
+        let mut config = mock_config();
+        config.level_up_reward = Uint128::from(50u128);
+        instantiate(deps.as_mut(), env.clone(), info.clone(), InstantiateMsg { admin: None, config }).unwrap();
+        fund_treasury(deps.as_mut(), 1000);
+
+        // 未授予 Pauser 角色的地址不能暂停
+        let err = execute(deps.as_mut(), env.clone(), message_info(&pauser, &[]), ExecuteMsg::Pause {
+            scope: PauseScope::Claims,
+        }).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::GrantRole {
+            role: Role::Pauser,
+            account: pauser.to_string(),
+        }).unwrap();
+
+        let distribute = ExecuteMsg::DistributeReward {
+            user: USER.to_string(),
+            amount: Uint128::from(100u128),
+            activity_type: ActivityType::Custom { activity_id: "test".to_string() },
+            event_id: None,
+        };
+        execute(deps.as_mut(), env.clone(), info.clone(), distribute.clone()).unwrap();
+
+        // 暂停领取不影响分发
+        execute(deps.as_mut(), env.clone(), message_info(&pauser, &[]), ExecuteMsg::Pause {
+            scope: PauseScope::Claims,
+        }).unwrap();
+        let user = Addr::unchecked(USER);
+        let claim = ExecuteMsg::ClaimReward { reward_id: "reward_0".to_string() };
+        let err = execute(deps.as_mut(), env.clone(), message_info(&user, &[]), claim.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Paused { scope: "claims".to_string() });
+        let err = execute(deps.as_mut(), env.clone(), message_info(&user, &[]), ExecuteMsg::ClaimAll { limit: None })
+            .unwrap_err();
+        assert_eq!(err, ContractError::Paused { scope: "claims".to_string() });
+        execute(deps.as_mut(), env.clone(), info.clone(), distribute.clone()).unwrap();
+
+        // 暂停分发时升级不发放升级奖励
+        execute(deps.as_mut(), env.clone(), message_info(&pauser, &[]), ExecuteMsg::Pause {
+            scope: PauseScope::Distribution,
+        }).unwrap();
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::AddPoints {
+            user: USER.to_string(),
+            points: 1000,
+        }).unwrap();
+        assert!(res.attributes.iter().any(|a| a.key == "level_up_reward_skipped" && a.value == "paused"));
+        assert!(res.attributes.iter().all(|a| a.key != "level_up_reward_id"));
+        execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::Unpause { scope: PauseScope::Distribution })
+            .unwrap();
+
+        // All 覆盖全部范围，资产无法转出
+        execute(deps.as_mut(), env.clone(), message_info(&pauser, &[]), ExecuteMsg::Pause { scope: PauseScope::All })
+            .unwrap();
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), distribute).unwrap_err();
+        assert_eq!(err, ContractError::Paused { scope: "all".to_string() });
+        let halted = [
+            ExecuteMsg::CancelReward { reward_id: "reward_1".to_string() },
+            ExecuteMsg::WithdrawTreasury {
+                asset: AssetInfo::Native { denom: "uluckee".to_string() },
+                amount: Uint128::from(10u128),
+                recipient: None,
+            },
+            ExecuteMsg::WithdrawNft { collection: "collection".to_string(), token_id: "1".to_string(), recipient: None },
+        ];
+        for msg in halted {
+            let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+            assert_eq!(err, ContractError::Paused { scope: "all".to_string() });
+        }
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::CreateRule {
+            rule: mock_rule(vec![], 100, Decimal::one()),
+        }).unwrap_err();
+        assert_eq!(err, ContractError::Paused { scope: "all".to_string() });
+
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::PauseStatus {}).unwrap();
+        let status: PauseStatusResponse = from_json(&res).unwrap();
+        let scopes: Vec<_> = status.paused.into_iter().map(|p| p.scope).collect();
+        assert_eq!(scopes, vec![PauseScope::All, PauseScope::Claims]);
+
+        for scope in [PauseScope::All, PauseScope::Claims] {
+            execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::Unpause { scope }).unwrap();
+        }
+        execute(deps.as_mut(), env, message_info(&user, &[]), claim).unwrap();
+    }
+
+    #[test]
+    fn test_admin_transfer_and_roles() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let creator = deps.api.addr_make("creator");
+        let successor = deps.api.addr_make("successor");
+        let manager = deps.api.addr_make("manager");
+        let info = message_info(&creator, &[]);
+
+        instantiate(deps.as_mut(), env.clone(), info.clone(), InstantiateMsg { admin: None, config: mock_config() })
+            .unwrap();
+
+        // 角色只授予对应操作
+        let create_rule = ExecuteMsg::CreateRule { rule: mock_rule(vec![], 100, Decimal::one()) };
+        let err = execute(deps.as_mut(), env.clone(), message_info(&manager, &[]), create_rule.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::GrantRole {
+            role: Role::RuleManager,
+            account: manager.to_string(),
+        }).unwrap();
+        execute(deps.as_mut(), env.clone(), message_info(&manager, &[]), create_rule.clone()).unwrap();
+        let err = execute(deps.as_mut(), env.clone(), message_info(&manager, &[]), ExecuteMsg::AddPoints {
+            user: USER.to_string(),
+            points: 10,
+        }).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::RoleMembers {
+            role: Role::RuleManager,
+            start_after: None,
+            limit: None,
+        }).unwrap();
+        let res: RoleMembersResponse = from_json(&res).unwrap();
+        assert_eq!(res.members, vec![manager.clone()]);
+
+        // 两步转移：提名后旧管理员仍有效，接受后生效
+        execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::ProposeAdmin {
+            new_admin: successor.to_string(),
+        }).unwrap();
+        let err = execute(deps.as_mut(), env.clone(), message_info(&manager, &[]), ExecuteMsg::AcceptAdmin {})
+            .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), env.clone(), message_info(&successor, &[]), ExecuteMsg::AcceptAdmin {}).unwrap();
+
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::Admin {}).unwrap();
+        let res: AdminResponse = from_json(&res).unwrap();
+        assert_eq!(res.admin, Some(successor.clone()));
+        assert_eq!(res.pending_admin, None);
+        let err = execute(deps.as_mut(), env.clone(), info, ExecuteMsg::RevokeRole {
+            role: Role::RuleManager,
+            account: manager.to_string(),
+        }).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        // 放弃管理员后角色仍然有效
+        execute(deps.as_mut(), env.clone(), message_info(&successor, &[]), ExecuteMsg::RenounceAdmin {}).unwrap();
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::Admin {}).unwrap();
+        let res: AdminResponse = from_json(&res).unwrap();
+        assert_eq!(res.admin, None);
+        execute(deps.as_mut(), env.clone(), message_info(&manager, &[]), create_rule).unwrap();
+        let err = execute(deps.as_mut(), env, message_info(&successor, &[]), ExecuteMsg::UpdateConfig {
+            config: mock_config(),
+        }).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+    }
+
+    #[test]
+    fn test_points_exchange_withdraws_after_mint() {
+        use cosmwasm_std::SubMsgResponse;
+
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = message_info(&Addr::unchecked("creator"), &[]);
+        let (ft_addr, registry_addr) = setup_points_exchange(&mut deps, mock_config());
+        deps.querier.update_wasm(registry_points(5000));
+
+        let exchange = |points: u128| ExecuteMsg::MintForPoints {
+            user: USER.to_string(),
+            points_amount: Uint128::from(points),
+        };
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), exchange(6000)).unwrap_err();
+        assert_eq!(err, ContractError::InsufficientPoints {});
+
+        // Registry 须被授予 query_points 权限才能作为积分来源
+        let set_capabilities = |capabilities: Vec<String>| ExecuteMsg::UpdateContract {
+            contract_type: ContractType::Registry,
+            status: None,
+            capabilities: Some(capabilities),
+        };
+        execute(deps.as_mut(), env.clone(), info.clone(), set_capabilities(vec![])).unwrap();
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), exchange(2000)).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), env.clone(), info.clone(), set_capabilities(vec![CAPABILITY_QUERY_POINTS.to_string()]))
+            .unwrap();
+
+        // 铸币以子消息发出，积分在回调中扣除
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), exchange(2000)).unwrap();
+        assert_eq!(res.messages.len(), 1);
+        let submsg = res.messages[0].clone();
+        assert_eq!(submsg.msg, CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: ft_addr.to_string(),
+            msg: to_json_binary(&Cw20ExecuteMsg::Mint { recipient: USER.to_string(), amount: Uint128::from(2000u128) })
+                .unwrap(),
+            funds: vec![],
+        }));
+
+        #[allow(deprecated)]
+        let ok = SubMsgResult::Ok(SubMsgResponse { events: vec![], data: None, msg_responses: vec![] });
+        let res = reply(deps.as_mut(), env.clone(), Reply {
+            id: submsg.id,
+            payload: submsg.payload.clone(),
+            gas_used: 0,
+            result: ok,
+        }).unwrap();
+        assert_eq!(res.messages[0].msg, CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: registry_addr.to_string(),
+            msg: to_json_binary(&RegistryExecuteMsg::WithdrawPoints {
+                user: USER.to_string(),
+                amount: Uint128::from(2000u128),
+            }).unwrap(),
+            funds: vec![],
+        }));
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::Reward { reward_id: "reward_0".to_string() }).unwrap();
+        let res: RewardResponse = from_json(&res).unwrap();
+        assert_eq!(res.reward.status, RewardStatus::Claimed);
+
+        // 铸币失败时作废兑换记录，不扣除积分
+        let res = execute(deps.as_mut(), env.clone(), info, exchange(1000)).unwrap();
+        let submsg = res.messages[0].clone();
+        let res = reply(deps.as_mut(), env.clone(), Reply {
+            id: submsg.id,
+            payload: submsg.payload,
+            gas_used: 0,
+            result: SubMsgResult::Err("minting disabled".to_string()),
+        }).unwrap();
+        assert!(res.messages.is_empty());
+        let res = query(deps.as_ref(), env, QueryMsg::Reward { reward_id: "reward_1".to_string() }).unwrap();
+        let res: RewardResponse = from_json(&res).unwrap();
+        assert_eq!(res.reward.status, RewardStatus::Cancelled);
+    }
+
+    #[test]
+    fn test_points_exchange_rate_and_daily_caps() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let info = message_info(&Addr::unchecked("creator"), &[]);
+
+        let mut config = mock_config();
+        config.points_exchange = PointsExchangeConfig {
+            rate: Decimal::percent(50),
+            min_points: Uint128::from(100u128),
+            max_points: Uint128::from(2500u128),
+            user_daily_cap: Uint128::from(3000u128),
+            global_daily_cap: Uint128::from(4000u128),
+        };
+        setup_points_exchange(&mut deps, config);
+        deps.querier.update_wasm(registry_points(1_000_000));
+
+        let exchange = |user: &str, points: u128| ExecuteMsg::MintForPoints {
+            user: user.to_string(),
+            points_amount: Uint128::from(points),
+        };
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), exchange(USER, 50)).unwrap_err();
+        assert_eq!(err, ContractError::InvalidAmount {});
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), exchange(USER, 3000)).unwrap_err();
+        assert_eq!(err, ContractError::ExchangeLimitExceeded { limit: "max_points".to_string() });
+
+        // 按 0.5 的比例折算代币
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), exchange(USER, 2000)).unwrap();
+        assert!(res.attributes.iter().any(|a| a.key == "token_amount" && a.value == "1000"));
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), exchange(USER, 1500)).unwrap_err();
+        assert_eq!(err, ContractError::ExchangeLimitExceeded { limit: "user_daily_cap".to_string() });
+        execute(deps.as_mut(), env.clone(), info.clone(), exchange("other", 1500)).unwrap();
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), exchange("third", 1000)).unwrap_err();
+        assert_eq!(err, ContractError::ExchangeLimitExceeded { limit: "global_daily_cap".to_string() });
+
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::ExchangeQuota { user: USER.to_string() }).unwrap();
+        let quota: ExchangeQuotaResponse = from_json(&res).unwrap();
+        assert_eq!(quota.user_used, Uint128::from(2000u128));
+        assert_eq!(quota.user_remaining, Some(Uint128::from(1000u128)));
+        assert_eq!(quota.global_remaining, Some(Uint128::from(500u128)));
+
+        // 24 小时内的兑换始终计入额度，不因整点分桶提前释放
+        let exchanged_at = env.block.time;
+        env.block.time = exchanged_at.plus_hours(23).plus_minutes(59);
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::ExchangeQuota { user: USER.to_string() }).unwrap();
+        let quota: ExchangeQuotaResponse = from_json(&res).unwrap();
+        assert_eq!(quota.user_used, Uint128::from(2000u128));
+
+        // 离开 24 小时前所在的小时桶后额度恢复
+        env.block.time = exchanged_at.plus_hours(25);
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::ExchangeQuota { user: USER.to_string() }).unwrap();
+        let quota: ExchangeQuotaResponse = from_json(&res).unwrap();
+        assert_eq!(quota.user_used, Uint128::zero());
+        assert_eq!(quota.global_remaining, Some(Uint128::from(4000u128)));
+        execute(deps.as_mut(), env, info, exchange(USER, 2500)).unwrap();
+    }
+
+    #[test]
+    fn test_nft_rewards_mint_and_escrow() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let creator = Addr::unchecked("creator");
+        let info = message_info(&creator, &[]);
+        let user_info = message_info(&Addr::unchecked(USER), &[]);
+        instantiate(deps.as_mut(), env.clone(), info.clone(), InstantiateMsg { admin: None, config: mock_config() })
+            .unwrap();
+
+        // 由注册的 Nft 合约按模板铸造
+        let mint_source = NftRewardSource::Mint {
+            token_id_template: "badge-{id}".to_string(),
+            token_uri: Some("ipfs://badge".to_string()),
+            extension: None,
+        };
+        let distribute = |source: NftRewardSource| ExecuteMsg::DistributeNftReward {
+            user: USER.to_string(),
+            source,
+            activity_type: ActivityType::Custom { activity_id: "test".to_string() },
+            event_id: None,
+        };
+        let nft_addr = deps.api.addr_make("nft");
+        execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::RegisterContract {
+            contract_type: ContractType::Nft,
+            contract_addr: nft_addr.to_string(),
+            capabilities: vec![],
+        }).unwrap();
+        execute(deps.as_mut(), env.clone(), info.clone(), distribute(mint_source)).unwrap();
+        let res = execute(deps.as_mut(), env.clone(), user_info.clone(), ExecuteMsg::ClaimReward {
+            reward_id: "reward_0".to_string(),
+        }).unwrap();
+        assert_eq!(res.messages[0].msg, CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: nft_addr.to_string(),
+            msg: to_json_binary(&Cw721ExecuteMsg::Mint {
+                token_id: "badge-0".to_string(),
+                owner: USER.to_string(),
+                token_uri: Some("ipfs://badge".to_string()),
+                extension: None,
+            }).unwrap(),
+            funds: vec![],
+        }));
+
+        // 托管两枚 NFT，分发时取 token_id 最小的一枚
+        let collection = deps.api.addr_make("collection");
+        for token_id in ["7", "8"] {
+            execute(deps.as_mut(), env.clone(), message_info(&collection, &[]), ExecuteMsg::ReceiveNft(
+                Cw721ReceiveMsg {
+                    sender: creator.to_string(),
+                    token_id: token_id.to_string(),
+                    msg: to_json_binary(&ReceiveNftMsg::DepositEscrow {}).unwrap(),
+                },
+            )).unwrap();
+        }
+        let escrow_source = |token_id: Option<&str>| NftRewardSource::Escrow {
+            collection: collection.to_string(),
+            token_id: token_id.map(str::to_string),
+        };
+        execute(deps.as_mut(), env.clone(), info.clone(), distribute(escrow_source(None))).unwrap();
+        let escrowed = |deps: Deps| -> Vec<String> {
+            let res = query(deps, mock_env(), QueryMsg::NftEscrow {
+                collection: collection.to_string(),
+                start_after: None,
+                limit: None,
+            }).unwrap();
+            from_json::<NftEscrowResponse>(&res).unwrap().token_ids
+        };
+        assert_eq!(escrowed(deps.as_ref()), vec!["8".to_string()]);
+
+        // 已分配的 NFT 不能取回，作废奖励后退回托管
+        let withdraw = ExecuteMsg::WithdrawNft {
+            collection: collection.to_string(),
+            token_id: "7".to_string(),
+            recipient: None,
+        };
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), withdraw.clone()).unwrap_err();
+        assert_eq!(err, ContractError::NftNotAvailable {});
+        execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::CancelReward {
+            reward_id: "reward_1".to_string(),
+        }).unwrap();
+        assert_eq!(escrowed(deps.as_ref()), vec!["7".to_string(), "8".to_string()]);
+        execute(deps.as_mut(), env.clone(), info.clone(), withdraw).unwrap();
+
+        // 指定 token_id 分发并领取
+        execute(deps.as_mut(), env.clone(), info.clone(), distribute(escrow_source(Some("8")))).unwrap();
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), distribute(escrow_source(None))).unwrap_err();
+        assert_eq!(err, ContractError::NftNotAvailable {});
+        let res = execute(deps.as_mut(), env.clone(), user_info, ExecuteMsg::ClaimReward {
+            reward_id: "reward_2".to_string(),
+        }).unwrap();
+        assert_eq!(res.messages[0].msg, CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: collection.to_string(),
+            msg: to_json_binary(&Cw721ExecuteMsg::TransferNft {
+                recipient: USER.to_string(),
+                token_id: "8".to_string(),
+            }).unwrap(),
+            funds: vec![],
+        }));
+
+        // NFT 规则必须指定来源
+        let rule = RuleDetails {
+            rewards: vec![RewardDefinition {
+                reward_type: RewardType::Nft,
+                amount: Uint128::one(),
+                multiplier: Decimal::one(),
+                conditions: vec![],
+                nft: None,
+                vesting: None,
+            }],
+            ..mock_rule(vec![], 0, Decimal::one())
+        };
+        let err = execute(deps.as_mut(), env, info, ExecuteMsg::CreateRule { rule }).unwrap_err();
+        assert_eq!(err, ContractError::InvalidConfiguration {});
+    }
+
+    #[test]
+    fn test_vesting_rewards_unlock_over_time() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let creator = Addr::unchecked("creator");
+        let info = message_info(&creator, &[]);
+        let user_info = message_info(&Addr::unchecked(USER), &[]);
+
+        let mut config = mock_config();
+        config.auto_claim_enabled = true;
+        instantiate(deps.as_mut(), env.clone(), info.clone(), InstantiateMsg { admin: None, config }).unwrap();
+        fund_treasury(deps.as_mut(), 10_000);
+
+        // cliff 之后每 300 秒解锁一次，1200 秒后全部解锁
+        let mut rule = mock_rule(vec![], 1200, Decimal::one());
+        rule.rewards[0].vesting = Some(VestingSchedule { cliff_seconds: 1300, duration_seconds: 1200, period_seconds: 300 });
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::CreateRule { rule: rule.clone() })
+            .unwrap_err();
+        assert_eq!(err, ContractError::InvalidConfiguration {});
+        rule.rewards[0].vesting = Some(VestingSchedule { cliff_seconds: 100, duration_seconds: 1200, period_seconds: 300 });
+        execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::CreateRule { rule }).unwrap();
+
+        // 解锁中的奖励不会自动领取
+        let res = execute(deps.as_mut(), env.clone(), info, ExecuteMsg::ReportActivity {
+            user: USER.to_string(),
+            activity_type: ActivityType::Custom { activity_id: "test".to_string() },
+            value: Uint128::zero(),
+            event_id: None,
+        }).unwrap();
+        assert!(res.messages.is_empty());
+        assert_eq!(query_treasury(deps.as_ref())[0].committed, Uint128::from(1200u128));
+
+        let claim_vested = ExecuteMsg::ClaimVested { reward_id: "reward_0".to_string() };
+        let err = execute(deps.as_mut(), env.clone(), user_info.clone(), ExecuteMsg::ClaimReward {
+            reward_id: "reward_0".to_string(),
+        }).unwrap_err();
+        assert_eq!(err, ContractError::RewardVesting {});
+        env.block.time = env.block.time.plus_seconds(50);
+        let err = execute(deps.as_mut(), env.clone(), user_info.clone(), claim_vested.clone()).unwrap_err();
+        assert_eq!(err, ContractError::NothingVested {});
+
+        // 650 秒时按 600 秒解锁一半
+        env.block.time = env.block.time.plus_seconds(600);
+        let res = execute(deps.as_mut(), env.clone(), user_info.clone(), claim_vested.clone()).unwrap();
+        assert_eq!(res.messages[0].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: USER.to_string(),
+            amount: coins(600, "uluckee"),
+        }));
+
+        env.block.time = env.block.time.plus_seconds(350);
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::VestingStatus { user: USER.to_string() }).unwrap();
+        let status: VestingStatusResponse = from_json(&res).unwrap();
+        assert_eq!(status.locked, Uint128::from(300u128));
+        assert_eq!(status.unlocked, Uint128::from(300u128));
+        assert_eq!(status.claimed, Uint128::from(600u128));
+
+        // 全部解锁后领取剩余部分，奖励记为 Claimed
+        env.block.time = env.block.time.plus_seconds(300);
+        let res = execute(deps.as_mut(), env.clone(), user_info.clone(), claim_vested.clone()).unwrap();
+        assert_eq!(res.messages[0].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: USER.to_string(),
+            amount: coins(600, "uluckee"),
+        }));
+        let err = execute(deps.as_mut(), env.clone(), user_info, claim_vested).unwrap_err();
+        assert_eq!(err, ContractError::RewardAlreadyClaimed {});
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::VestingStatus { user: USER.to_string() }).unwrap();
+        let status: VestingStatusResponse = from_json(&res).unwrap();
+        assert!(status.rewards.is_empty());
+        assert_eq!(query_treasury(deps.as_ref())[0].committed, Uint128::zero());
+    }
+
+    // ===== cw-multi-test：与真实 cw20-base 集成 =====
+
+    mod multitest {
+        use cosmwasm_std::{Addr, Empty, Uint128};
+        use cw20::{BalanceResponse, Cw20QueryMsg, MinterResponse};
+        use cw_multi_test::error::AnyResult;
+        use cw_multi_test::{App, AppResponse, Contract, ContractWrapper, Executor};
+
+        use super::mock_config;
+        use crate::error::ContractError;
+        use crate::msg::*;
+
+        fn incentive_contract() -> Box<dyn Contract<Empty>> {
+            Box::new(
+                ContractWrapper::new(crate::contract::execute, crate::contract::instantiate, crate::contract::query)
+                    .with_reply(crate::contract::reply)
+                    .with_migrate(crate::contract::migrate),
+            )
+        }
+
+        fn cw20_contract() -> Box<dyn Contract<Empty>> {
+            Box::new(ContractWrapper::new(
+                cw20_base::contract::execute,
+                cw20_base::contract::instantiate,
+                cw20_base::contract::query,
+            ))
+        }
+
+        struct Suite {
+            app: App,
+            admin: Addr,
+            incentive: Addr,
+            token: Addr,
+        }
+
+        impl Suite {
+            /// 部署激励合约与 cw20-base，`minter` 为 None 时由激励合约担任 minter
+            fn new(mint_cap: Uint128, minter: Option<&str>) -> Self {
+                let mut app = App::default();
+                let admin = app.api().addr_make("admin");
+
+                let mut config = mock_config();
+                config.payout_method = PayoutMethod::Cw20Mint;
+                config.mint_cap = mint_cap;
+                let incentive_id = app.store_code(incentive_contract());
+                let incentive = app
+                    .instantiate_contract(
+                        incentive_id,
+                        admin.clone(),
+                        &InstantiateMsg { admin: None, config },
+                        &[],
+                        "luckee-incentive",
+                        None,
+                    )
+                    .unwrap();
+
+                let cw20_id = app.store_code(cw20_contract());
+                let token = app
+                    .instantiate_contract(
+                        cw20_id,
+                        admin.clone(),
+                        &cw20_base::msg::InstantiateMsg {
+                            name: "Luckee".to_string(),
+                            symbol: "LUCK".to_string(),
+                            decimals: 6,
+                            initial_balances: vec![],
+                            mint: Some(MinterResponse {
+                                minter: minter.map_or_else(|| incentive.clone(), |name| app.api().addr_make(name)).to_string(),
+                                cap: None,
+                            }),
+                            marketing: None,
+                        },
+                        &[],
+                        "luckee-ft",
+                        None,
+                    )
+                    .unwrap();
+
+                app.execute_contract(admin.clone(), incentive.clone(), &ExecuteMsg::RegisterContract {
+                    contract_type: ContractType::Ft,
+                    contract_addr: token.to_string(),
+                    capabilities: vec![],
+                }, &[])
+                .unwrap();
+
+                Suite { app, admin, incentive, token }
+            }
+
+            fn distribute(&mut self, user: &Addr, amount: u128) -> AppResponse {
+                self.app
+                    .execute_contract(self.admin.clone(), self.incentive.clone(), &ExecuteMsg::DistributeReward {
+                        user: user.to_string(),
+                        amount: Uint128::from(amount),
+                        activity_type: ActivityType::Custom { activity_id: "test".to_string() },
+                        event_id: None,
+                    }, &[])
+                    .unwrap()
+            }
+
+            fn claim(&mut self, user: &Addr, reward_id: &str) -> AnyResult<AppResponse> {
+                self.app.execute_contract(user.clone(), self.incentive.clone(), &ExecuteMsg::ClaimReward {
+                    reward_id: reward_id.to_string(),
+                }, &[])
+            }
+
+            fn balance(&self, user: &Addr) -> Uint128 {
+                let res: BalanceResponse = self
+                    .app
+                    .wrap()
+                    .query_wasm_smart(&self.token, &Cw20QueryMsg::Balance { address: user.to_string() })
+                    .unwrap();
+                res.balance
+            }
+
+            fn mint_status(&self) -> MintStatusResponse {
+                self.app.wrap().query_wasm_smart(&self.incentive, &QueryMsg::MintStatus {}).unwrap()
+            }
+
+            fn reward_status(&self, reward_id: &str) -> RewardStatus {
+                let res: RewardResponse = self
+                    .app
+                    .wrap()
+                    .query_wasm_smart(&self.incentive, &QueryMsg::Reward { reward_id: reward_id.to_string() })
+                    .unwrap();
+                res.reward.status
+            }
+        }
+
+        #[test]
+        fn claim_mints_cw20_tokens() {
+            let mut suite = Suite::new(Uint128::zero(), None);
+            let user = suite.app.api().addr_make("user");
+
+            suite.distribute(&user, 700);
+            suite.claim(&user, "reward_0").unwrap();
+
+            assert_eq!(suite.balance(&user), Uint128::from(700u128));
+            assert_eq!(suite.reward_status("reward_0"), RewardStatus::Claimed);
+            let status = suite.mint_status();
+            assert_eq!(status.total_minted, Uint128::from(700u128));
+            assert_eq!(status.remaining, None);
+        }
+
+        #[test]
+        fn claim_respects_mint_cap() {
+            let mut suite = Suite::new(Uint128::from(1500u128), None);
+            let user = suite.app.api().addr_make("user");
+
+            suite.distribute(&user, 1000);
+            suite.distribute(&user, 1000);
+            suite.claim(&user, "reward_0").unwrap();
+
+            let err = suite.claim(&user, "reward_1").unwrap_err();
+            assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::MintCapExceeded {});
+            assert_eq!(suite.reward_status("reward_1"), RewardStatus::Pending);
+            assert_eq!(suite.balance(&user), Uint128::from(1000u128));
+            assert_eq!(suite.mint_status().remaining, Some(Uint128::from(500u128)));
+        }
+
+        #[test]
+        fn failed_mint_reverts_claim() {
+            // 激励合约不是 minter，cw20-base 拒绝铸币
+            let mut suite = Suite::new(Uint128::zero(), Some("other"));
+            let user = suite.app.api().addr_make("user");
+
+            suite.distribute(&user, 700);
+            let err = suite.claim(&user, "reward_0").unwrap_err();
+            assert!(matches!(err.downcast::<ContractError>().unwrap(), ContractError::MintFailed { .. }));
+
+            assert_eq!(suite.reward_status("reward_0"), RewardStatus::Pending);
+            assert_eq!(suite.balance(&user), Uint128::zero());
+            assert_eq!(suite.mint_status().total_minted, Uint128::zero());
+        }
+    }
 }
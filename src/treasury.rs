@@ -0,0 +1,129 @@
+use cosmwasm_std::{Addr, QuerierWrapper, StdResult, Storage, Uint128};
+use cw20::{BalanceResponse, Cw20QueryMsg};
+
+use crate::error::ContractError;
+use crate::msg::{AssetInfo, MintStatusResponse, TreasuryBalance};
+use crate::state::{TOTAL_MINTED, TREASURY};
+
+// ===== 金库记账 =====
+//
+// 每种资产分为两部分：
+// - available: 可用于新奖励的余额
+// - committed: 已分配给未领取奖励、尚未转出的余额
+// 合约实际持有的资产始终不少于 available + committed。
+
+fn load_balance(storage: &dyn Storage, asset: &AssetInfo) -> Result<TreasuryBalance, ContractError> {
+    Ok(TREASURY.may_load(storage, asset.key())?.unwrap_or_else(|| TreasuryBalance {
+        asset: asset.clone(),
+        available: Uint128::zero(),
+        committed: Uint128::zero(),
+    }))
+}
+
+/// 可用于新奖励的余额
+pub fn available(storage: &dyn Storage, asset: &AssetInfo) -> Result<Uint128, ContractError> {
+    Ok(load_balance(storage, asset)?.available)
+}
+
+/// 资产入库，增加可用余额
+pub fn deposit(storage: &mut dyn Storage, asset: &AssetInfo, amount: Uint128) -> Result<TreasuryBalance, ContractError> {
+    let mut balance = load_balance(storage, asset)?;
+    balance.available = balance.available.checked_add(amount).map_err(cosmwasm_std::StdError::from)?;
+    TREASURY.save(storage, asset.key(), &balance)?;
+    Ok(balance)
+}
+
+/// 从可用余额中提取资产
+pub fn withdraw(storage: &mut dyn Storage, asset: &AssetInfo, amount: Uint128) -> Result<TreasuryBalance, ContractError> {
+    let mut balance = load_balance(storage, asset)?;
+    balance.available = balance
+        .available
+        .checked_sub(amount)
+        .map_err(|_| ContractError::InsufficientBalance {})?;
+    TREASURY.save(storage, asset.key(), &balance)?;
+    Ok(balance)
+}
+
+/// 为新奖励锁定资产：available -> committed
+pub fn commit(storage: &mut dyn Storage, asset: &AssetInfo, amount: Uint128) -> Result<(), ContractError> {
+    let mut balance = load_balance(storage, asset)?;
+    balance.available = balance
+        .available
+        .checked_sub(amount)
+        .map_err(|_| ContractError::InsufficientBalance {})?;
+    balance.committed += amount;
+    TREASURY.save(storage, asset.key(), &balance)?;
+    Ok(())
+}
+
+/// 奖励被领取后，已锁定资产转出合约
+pub fn settle(storage: &mut dyn Storage, asset: &AssetInfo, amount: Uint128) -> Result<(), ContractError> {
+    let mut balance = load_balance(storage, asset)?;
+    balance.committed = balance
+        .committed
+        .checked_sub(amount)
+        .map_err(|_| ContractError::InsufficientBalance {})?;
+    TREASURY.save(storage, asset.key(), &balance)?;
+    Ok(())
+}
+
+/// 奖励过期或取消后，锁定资产退回可用余额：committed -> available
+pub fn release(storage: &mut dyn Storage, asset: &AssetInfo, amount: Uint128) -> Result<(), ContractError> {
+    let mut balance = load_balance(storage, asset)?;
+    balance.committed = balance
+        .committed
+        .checked_sub(amount)
+        .map_err(|_| ContractError::InsufficientBalance {})?;
+    balance.available += amount;
+    TREASURY.save(storage, asset.key(), &balance)?;
+    Ok(())
+}
+
+/// 合约实际持有的资产数量，用于与账面 available + committed 对账
+pub fn held_balance(querier: &QuerierWrapper, asset: &AssetInfo, holder: &Addr) -> StdResult<Uint128> {
+    match asset {
+        AssetInfo::Native { denom } => Ok(querier.query_balance(holder, denom)?.amount),
+        AssetInfo::Cw20 { contract_addr } => {
+            let res: BalanceResponse = querier.query_wasm_smart(
+                contract_addr,
+                &Cw20QueryMsg::Balance { address: holder.to_string() },
+            )?;
+            Ok(res.balance)
+        }
+    }
+}
+
+// ===== 铸币额度 =====
+//
+// 铸造模式下奖励不占用金库，改为累计铸造量并受 `mint_cap` 限制。
+
+/// 记录一次铸币，超出上限时拒绝；`mint_cap` 为 0 表示不限制
+pub fn record_mint(storage: &mut dyn Storage, mint_cap: Uint128, amount: Uint128) -> Result<Uint128, ContractError> {
+    let total = TOTAL_MINTED
+        .may_load(storage)?
+        .unwrap_or_default()
+        .checked_add(amount)
+        .map_err(cosmwasm_std::StdError::from)?;
+    if !mint_cap.is_zero() && total > mint_cap {
+        return Err(ContractError::MintCapExceeded {});
+    }
+    TOTAL_MINTED.save(storage, &total)?;
+    Ok(total)
+}
+
+/// 铸币失败后退回已记录的铸造量
+pub fn revert_mint(storage: &mut dyn Storage, amount: Uint128) -> Result<(), ContractError> {
+    let total = TOTAL_MINTED.may_load(storage)?.unwrap_or_default();
+    TOTAL_MINTED.save(storage, &total.saturating_sub(amount))?;
+    Ok(())
+}
+
+/// 累计铸造量与剩余额度
+pub fn mint_status(storage: &dyn Storage, mint_cap: Uint128) -> StdResult<MintStatusResponse> {
+    let total_minted = TOTAL_MINTED.may_load(storage)?.unwrap_or_default();
+    Ok(MintStatusResponse {
+        total_minted,
+        mint_cap,
+        remaining: (!mint_cap.is_zero()).then(|| mint_cap.saturating_sub(total_minted)),
+    })
+}
@@ -0,0 +1,48 @@
+use cosmwasm_std::{Timestamp, Uint128};
+
+use crate::error::ContractError;
+use crate::msg::{UserReward, VestingSchedule};
+
+// ===== 奖励解锁 =====
+//
+// 解锁从奖励创建时开始计时：
+// - cliff 之前全部锁定
+// - 之后按 elapsed / duration 的比例解锁，period 不为 0 时 elapsed 向下取整到 period 的整数倍
+// - 到达 duration 后全部解锁
+
+/// duration 须为正，cliff 与 period 不得超过 duration
+pub fn validate_schedule(schedule: &VestingSchedule) -> Result<(), ContractError> {
+    if schedule.duration_seconds == 0
+        || schedule.cliff_seconds > schedule.duration_seconds
+        || schedule.period_seconds > schedule.duration_seconds
+    {
+        return Err(ContractError::InvalidConfiguration {});
+    }
+    Ok(())
+}
+
+/// 截至 `now` 已解锁的数量（含已领取部分）
+pub fn vested_amount(reward: &UserReward, now: Timestamp) -> Uint128 {
+    let Some(vesting) = &reward.vesting else {
+        return reward.amount;
+    };
+    let schedule = &vesting.schedule;
+    let elapsed = now.seconds().saturating_sub(reward.created_at.seconds());
+    if elapsed < schedule.cliff_seconds {
+        return Uint128::zero();
+    }
+    if elapsed >= schedule.duration_seconds {
+        return reward.amount;
+    }
+    let elapsed = match schedule.period_seconds {
+        0 => elapsed,
+        period => elapsed - elapsed % period,
+    };
+    reward.amount.multiply_ratio(elapsed, schedule.duration_seconds)
+}
+
+/// 当前可领取的数量
+pub fn releasable(reward: &UserReward, now: Timestamp) -> Uint128 {
+    let claimed = reward.vesting.as_ref().map_or(Uint128::zero(), |vesting| vesting.claimed);
+    vested_amount(reward, now).saturating_sub(claimed)
+}